@@ -0,0 +1,515 @@
+use std::ops::{Index, IndexMut};
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    geometry::{
+        add, closest_point_on_segment, dot, length, normalize, point_in_polygon, polygon_area,
+        scale, sub, Vec2,
+    },
+    particle::Particle,
+    polygon::{polygon_segment_contact, Contact, Polygon},
+    rectangle::Rectangle,
+};
+
+/// Side of the rectangular container.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edge {
+    Left = 0,
+    Right = 1,
+    Top = 2,
+    Bottom = 3,
+}
+
+pub const EDGES: [Edge; 4] = [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom];
+
+/// How one side of the rectangular container treats particles reaching it.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EdgeMode {
+    Reflect = 0,
+    /// Particles leaving through this side re-enter through the opposite one.
+    /// Only takes effect when the opposite side is periodic as well.
+    Periodic = 1,
+    /// Particles leaving through this side are removed from the universe.
+    Absorb = 2,
+}
+
+/// Modes of the four sides of the rectangular container, indexed by [`Edge`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Edges([EdgeMode; 4]);
+
+impl Edges {
+    pub fn is_periodic_x(&self) -> bool {
+        self[Edge::Left] == EdgeMode::Periodic && self[Edge::Right] == EdgeMode::Periodic
+    }
+
+    pub fn is_periodic_y(&self) -> bool {
+        self[Edge::Top] == EdgeMode::Periodic && self[Edge::Bottom] == EdgeMode::Periodic
+    }
+
+    /// Whether things bounce off `edge`. A periodic side whose opposite side
+    /// is not periodic behaves as a wall.
+    pub fn is_solid(&self, edge: Edge) -> bool {
+        match self[edge] {
+            EdgeMode::Reflect => true,
+            EdgeMode::Periodic => match edge {
+                Edge::Left | Edge::Right => !self.is_periodic_x(),
+                Edge::Top | Edge::Bottom => !self.is_periodic_y(),
+            },
+            EdgeMode::Absorb => false,
+        }
+    }
+}
+
+impl Index<Edge> for Edges {
+    type Output = EdgeMode;
+
+    fn index(&self, edge: Edge) -> &EdgeMode {
+        &self.0[edge as usize]
+    }
+}
+
+impl IndexMut<Edge> for Edges {
+    fn index_mut(&mut self, edge: Edge) -> &mut EdgeMode {
+        &mut self.0[edge as usize]
+    }
+}
+
+/// Shape of the vessel that keeps particles inside the universe.
+#[derive(Clone, Debug)]
+pub enum Container {
+    /// The `[0, width] × [0, height]` canvas, with one mode per side.
+    Rectangle {
+        edges: Edges,
+    },
+    Circle {
+        x: f64,
+        y: f64,
+        radius: f64,
+    },
+    /// Simple polygon, convex or concave, in either winding order.
+    Polygon {
+        vertices: Vec<Vec2>,
+    },
+}
+
+impl Container {
+    pub fn rectangle() -> Container {
+        Container::Rectangle {
+            edges: Edges([EdgeMode::Reflect; 4]),
+        }
+    }
+
+    /// Sets the mode of one side, switching back to the rectangular container
+    /// if needed.
+    pub fn set_edge_mode(&mut self, edge: Edge, mode: EdgeMode) {
+        if !matches!(self, Container::Rectangle { .. }) {
+            *self = Container::rectangle();
+        }
+
+        if let Container::Rectangle { edges } = self {
+            edges[edge] = mode;
+        }
+    }
+
+    /// Makes the left/right and top/bottom pairs periodic or reflecting,
+    /// switching back to the rectangular container if needed.
+    pub fn set_periodic(&mut self, periodic_x: bool, periodic_y: bool) {
+        if !matches!(self, Container::Rectangle { .. }) {
+            *self = Container::rectangle();
+        }
+
+        if let Container::Rectangle { edges } = self {
+            for (edge, periodic) in [
+                (Edge::Left, periodic_x),
+                (Edge::Right, periodic_x),
+                (Edge::Top, periodic_y),
+                (Edge::Bottom, periodic_y),
+            ] {
+                if periodic {
+                    edges[edge] = EdgeMode::Periodic;
+                } else if edges[edge] == EdgeMode::Periodic {
+                    edges[edge] = EdgeMode::Reflect;
+                }
+            }
+        }
+    }
+
+    pub fn is_periodic_x(&self) -> bool {
+        matches!(self, Container::Rectangle { edges } if edges.is_periodic_x())
+    }
+
+    pub fn is_periodic_y(&self) -> bool {
+        matches!(self, Container::Rectangle { edges } if edges.is_periodic_y())
+    }
+
+    /// The absorbing side that `aabb` has completely passed through, if any.
+    pub fn absorbing_edge(&self, aabb: &Rectangle, bounds: &Rectangle) -> Option<Edge> {
+        let edges = match self {
+            Container::Rectangle { edges } => edges,
+            _ => return None,
+        };
+
+        EDGES.iter().copied().find(|edge| {
+            edges[*edge] == EdgeMode::Absorb
+                && match edge {
+                    Edge::Left => aabb.max_x() < bounds.min_x(),
+                    Edge::Right => aabb.min_x() > bounds.max_x(),
+                    Edge::Top => aabb.max_y() < bounds.min_y(),
+                    Edge::Bottom => aabb.min_y() > bounds.max_y(),
+                }
+        })
+    }
+
+    /// Displacement from `b` to `a`. Across periodic edges this is the
+    /// minimum-image displacement, i.e. the shortest one through the seams.
+    pub fn displacement(&self, a: Vec2, b: Vec2, bounds: &Rectangle) -> Vec2 {
+        let (mut dx, mut dy) = sub(a, b);
+        if self.is_periodic_x() {
+            dx -= bounds.width() * (dx / bounds.width()).round();
+        }
+        if self.is_periodic_y() {
+            dy -= bounds.height() * (dy / bounds.height()).round();
+        }
+        (dx, dy)
+    }
+
+    /// `range` plus its periodic images wherever it sticks out past a
+    /// periodic edge, so spatial queries also see across the seams.
+    pub fn query_ranges(&self, range: &Rectangle, bounds: &Rectangle) -> Vec<Rectangle> {
+        let mut shifts_x = vec![0.0];
+        let mut shifts_y = vec![0.0];
+
+        if self.is_periodic_x() {
+            if range.min_x() < bounds.min_x() {
+                shifts_x.push(bounds.width());
+            }
+            if range.max_x() > bounds.max_x() {
+                shifts_x.push(-bounds.width());
+            }
+        }
+        if self.is_periodic_y() {
+            if range.min_y() < bounds.min_y() {
+                shifts_y.push(bounds.height());
+            }
+            if range.max_y() > bounds.max_y() {
+                shifts_y.push(-bounds.height());
+            }
+        }
+
+        let mut ranges = Vec::with_capacity(shifts_x.len() * shifts_y.len());
+        for shift_x in shifts_x.iter() {
+            for shift_y in shifts_y.iter() {
+                ranges.push(Rectangle::new(
+                    range.x + shift_x,
+                    range.y + shift_y,
+                    range.w,
+                    range.h,
+                ));
+            }
+        }
+        ranges
+    }
+
+    /// Smallest rectangle enclosing the container.
+    pub fn bounding_box(&self, bounds: &Rectangle) -> Rectangle {
+        match self {
+            Container::Rectangle { .. } => *bounds,
+            Container::Circle { x, y, radius } => Rectangle::new(*x, *y, *radius, *radius),
+            Container::Polygon { vertices } => {
+                let mut min = vertices[0];
+                let mut max = vertices[0];
+                for v in vertices.iter().skip(1) {
+                    min = (min.0.min(v.0), min.1.min(v.1));
+                    max = (max.0.max(v.0), max.1.max(v.1));
+                }
+                Rectangle::from_corners(min.0, min.1, max.0, max.1)
+            }
+        }
+    }
+
+    pub fn area(&self, bounds: &Rectangle) -> f64 {
+        match self {
+            Container::Rectangle { .. } => bounds.area(),
+            Container::Circle { radius, .. } => std::f64::consts::PI * radius * radius,
+            Container::Polygon { vertices } => polygon_area(vertices).abs(),
+        }
+    }
+
+    /// Length of the boundary that particles can push against, leaving out
+    /// periodic seams and absorbing edges.
+    pub fn wall_length(&self, bounds: &Rectangle) -> f64 {
+        match self {
+            Container::Rectangle { edges } => EDGES
+                .iter()
+                .filter(|&&edge| edges.is_solid(edge))
+                .map(|edge| match edge {
+                    Edge::Left | Edge::Right => bounds.height(),
+                    Edge::Top | Edge::Bottom => bounds.width(),
+                })
+                .sum(),
+            Container::Circle { radius, .. } => 2.0 * std::f64::consts::PI * radius,
+            Container::Polygon { vertices } => (0..vertices.len())
+                .map(|i| length(sub(vertices[(i + 1) % vertices.len()], vertices[i])))
+                .sum(),
+        }
+    }
+
+    /// Keeps `particle` inside the container, reflecting its velocity along
+    /// the boundary normal and scaling the reflected component by `restitution`.
+    /// Returns the absorbing side the particle left through, in which case
+    /// the caller should remove it.
+    pub fn bounce(
+        &self,
+        particle: &mut Particle,
+        bounds: &Rectangle,
+        restitution: f64,
+    ) -> Option<Edge> {
+        match self {
+            Container::Rectangle { edges } => {
+                let aabb = Rectangle::new(particle.x, particle.y, particle.radius, particle.radius);
+                if let Some(edge) = self.absorbing_edge(&aabb, bounds) {
+                    return Some(edge);
+                }
+
+                bounce_off_rectangle(particle, bounds, edges, restitution);
+                None
+            }
+            _ => {
+                if let Some(contact) = self.circle_contact(particle, 0.0) {
+                    particle.x += contact.normal.0 * contact.depth;
+                    particle.y += contact.normal.1 * contact.depth;
+
+                    let velocity_along_normal = dot((particle.dx, particle.dy), contact.normal);
+                    if velocity_along_normal < 0.0 {
+                        particle.dx -=
+                            (1.0 + restitution) * velocity_along_normal * contact.normal.0;
+                        particle.dy -=
+                            (1.0 + restitution) * velocity_along_normal * contact.normal.1;
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Contact with the container wall, normal pointing into the container.
+    /// Particles within `slop` of the wall count as touching it, with a
+    /// negative depth.
+    fn circle_contact(&self, particle: &Particle, slop: f64) -> Option<Contact> {
+        let center = (particle.x, particle.y);
+
+        match self {
+            Container::Rectangle { .. } => None,
+            Container::Circle { x, y, radius } => {
+                let offset = sub((*x, *y), center);
+                let distance = length(offset);
+                let depth = distance + particle.radius - radius;
+                if depth <= -slop || distance == 0.0 {
+                    return None;
+                }
+                let normal = scale(offset, 1.0 / distance);
+                Some(Contact {
+                    normal,
+                    depth,
+                    point: sub(center, scale(normal, particle.radius)),
+                })
+            }
+            Container::Polygon { vertices } => {
+                let n = vertices.len();
+                let (closest, distance) = (0..n)
+                    .map(|i| {
+                        let q =
+                            closest_point_on_segment(center, vertices[i], vertices[(i + 1) % n]);
+                        (q, length(sub(center, q)))
+                    })
+                    .fold(((0.0, 0.0), f64::INFINITY), |best, candidate| {
+                        if candidate.1 < best.1 {
+                            candidate
+                        } else {
+                            best
+                        }
+                    });
+
+                if distance == 0.0 {
+                    return None;
+                }
+
+                if point_in_polygon(center, vertices) {
+                    if distance >= particle.radius + slop {
+                        return None;
+                    }
+                    Some(Contact {
+                        normal: scale(sub(center, closest), 1.0 / distance),
+                        depth: particle.radius - distance,
+                        point: closest,
+                    })
+                } else {
+                    Some(Contact {
+                        normal: scale(sub(closest, center), 1.0 / distance),
+                        depth: particle.radius + distance,
+                        point: closest,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Moves `particle` back inside the container without touching its
+    /// velocity. Returns the inward wall normal if the particle is within
+    /// `slop` of a solid wall.
+    pub fn project(&self, particle: &mut Particle, bounds: &Rectangle, slop: f64) -> Option<Vec2> {
+        match self {
+            Container::Rectangle { edges } => {
+                let mut normal = (0.0, 0.0);
+                let walls = [
+                    (
+                        Edge::Left,
+                        (1.0, 0.0),
+                        bounds.min_x() - (particle.x - particle.radius),
+                    ),
+                    (
+                        Edge::Right,
+                        (-1.0, 0.0),
+                        particle.x + particle.radius - bounds.max_x(),
+                    ),
+                    (
+                        Edge::Top,
+                        (0.0, 1.0),
+                        bounds.min_y() - (particle.y - particle.radius),
+                    ),
+                    (
+                        Edge::Bottom,
+                        (0.0, -1.0),
+                        particle.y + particle.radius - bounds.max_y(),
+                    ),
+                ];
+
+                for (edge, wall_normal, depth) in walls.iter() {
+                    if !edges.is_solid(*edge) || *depth <= -slop {
+                        continue;
+                    }
+                    if *depth > 0.0 {
+                        particle.x += wall_normal.0 * depth;
+                        particle.y += wall_normal.1 * depth;
+                    }
+                    normal = add(normal, *wall_normal);
+                }
+
+                if normal == (0.0, 0.0) {
+                    None
+                } else {
+                    Some(normalize(normal))
+                }
+            }
+            _ => self.circle_contact(particle, slop).map(|contact| {
+                if contact.depth > 0.0 {
+                    particle.x += contact.normal.0 * contact.depth;
+                    particle.y += contact.normal.1 * contact.depth;
+                }
+                contact.normal
+            }),
+        }
+    }
+
+    pub fn collide_polygon(&self, polygon: &mut Polygon, bounds: &Rectangle, restitution: f64) {
+        match self {
+            Container::Rectangle { edges } => {
+                if self.is_periodic_x() {
+                    polygon.x = wrap(polygon.x, bounds.min_x(), bounds.width());
+                }
+                if self.is_periodic_y() {
+                    polygon.y = wrap(polygon.y, bounds.min_y(), bounds.height());
+                }
+
+                let solid = EDGES.map(|edge| edges.is_solid(edge));
+                polygon.bounce_off_walls(bounds, solid, restitution);
+            }
+            Container::Circle { x, y, radius } => {
+                let center = (*x, *y);
+                let deepest = polygon
+                    .world_vertices()
+                    .into_iter()
+                    .map(|vertex| (vertex, length(sub(vertex, center)) - radius))
+                    .fold(None, |best: Option<(Vec2, f64)>, candidate| match best {
+                        Some(b) if b.1 >= candidate.1 => Some(b),
+                        _ => Some(candidate),
+                    });
+
+                if let Some((point, depth)) = deepest {
+                    if depth > 0.0 {
+                        let contact = Contact {
+                            normal: scale(sub(center, point), 1.0 / (depth + radius)),
+                            depth,
+                            point,
+                        };
+                        polygon.resolve_static_contact(&contact, restitution);
+                    }
+                }
+            }
+            Container::Polygon { vertices } => {
+                let n = vertices.len();
+                for i in 0..n {
+                    if let Some(contact) =
+                        polygon_segment_contact(polygon, vertices[i], vertices[(i + 1) % n], 0.0)
+                    {
+                        polygon.resolve_static_contact(&contact, restitution);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn wrap(value: f64, min: f64, size: f64) -> f64 {
+    min + (value - min).rem_euclid(size)
+}
+
+fn bounce_off_rectangle(
+    particle: &mut Particle,
+    bounds: &Rectangle,
+    edges: &Edges,
+    restitution: f64,
+) {
+    if edges.is_periodic_x() {
+        let x = wrap(particle.x, bounds.min_x(), bounds.width());
+        particle.unwrap_x += particle.x - x;
+        particle.x = x;
+    }
+    if edges.is_periodic_y() {
+        let y = wrap(particle.y, bounds.min_y(), bounds.height());
+        particle.unwrap_y += particle.y - y;
+        particle.y = y;
+    }
+
+    let hits_left = edges.is_solid(Edge::Left) && particle.x - particle.radius < bounds.min_x();
+    let hits_right = edges.is_solid(Edge::Right) && particle.x + particle.radius > bounds.max_x();
+    let hits_top = edges.is_solid(Edge::Top) && particle.y - particle.radius < bounds.min_y();
+    let hits_bottom = edges.is_solid(Edge::Bottom) && particle.y + particle.radius > bounds.max_y();
+
+    // Check for bouncing off edges
+    if hits_left || hits_right {
+        particle.dx *= -restitution;
+    }
+    if hits_top || hits_bottom {
+        particle.dy *= -restitution;
+    }
+
+    if hits_right {
+        particle.x = bounds.max_x() - particle.radius;
+    }
+
+    if hits_left {
+        particle.x = bounds.min_x() + particle.radius;
+    }
+
+    if hits_bottom {
+        particle.y = bounds.max_y() - particle.radius;
+    }
+
+    if hits_top {
+        particle.y = bounds.min_y() + particle.radius;
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{boundary::Container, particle::Particle, rectangle::Rectangle};
+
+/// Distance within which bodies count as touching.
+pub const CONTACT_SLOP: f64 = 0.01;
+
+/// What happens when two particles touch.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CollisionMode {
+    Bounce = 0,
+    /// The particles merge into one, conserving mass and momentum.
+    Merge = 1,
+    /// The particles bounce, or shatter if the impact is energetic enough.
+    Fragment = 2,
+    /// The particles never touch but push and pull each other through the
+    /// pair potential.
+    Potential = 3,
+}
+
+/// Bounces two overlapping particles off each other and pushes them apart.
+/// `(dx, dy)` is the displacement from `other` to `particle`, `distance` its
+/// length, and only the normal component of the velocities is scaled by
+/// `restitution`.
+pub fn resolve_collision(
+    particle: &mut Particle,
+    other_particle: &mut Particle,
+    (dx, dy): (f64, f64),
+    distance: f64,
+    restitution: f64,
+) {
+    let angle = dy.atan2(dx);
+    let sin = angle.sin();
+    let cos = angle.cos();
+
+    let v1 = (
+        cos * particle.dx + sin * particle.dy,
+        cos * particle.dy - sin * particle.dx,
+    );
+    let v2 = (
+        cos * other_particle.dx + sin * other_particle.dy,
+        cos * other_particle.dy - sin * other_particle.dx,
+    );
+
+    let m1 = particle.mass;
+    let m2 = other_particle.mass;
+
+    let mut v1_final = (((m1 - m2) * v1.0 + 2.0 * m2 * v2.0) / (m1 + m2), v1.1);
+    let mut v2_final = (((m2 - m1) * v2.0 + 2.0 * m1 * v1.0) / (m1 + m2), v2.1);
+
+    v1_final = (v1_final.0 * restitution, v1_final.1);
+    v2_final = (v2_final.0 * restitution, v2_final.1);
+
+    particle.dx = cos * v1_final.0 - sin * v1_final.1;
+    particle.dy = cos * v1_final.1 + sin * v1_final.0;
+    other_particle.dx = cos * v2_final.0 - sin * v2_final.1;
+    other_particle.dy = cos * v2_final.1 + sin * v2_final.0;
+
+    let overlap = (particle.radius + other_particle.radius - distance) / 2.0;
+    particle.x += cos * overlap;
+    particle.y += sin * overlap;
+    other_particle.x -= cos * overlap;
+    other_particle.y -= sin * overlap;
+}
+
+/// Pairs of particles that may touch during this tick, found with a uniform
+/// grid sized after the largest particle and its travel this tick. Pairs
+/// whose collision masks exclude each other are left out.
+pub fn candidate_pairs(
+    particles: &[Particle],
+    container: &Container,
+    bounds: &Rectangle,
+) -> Vec<(usize, usize)> {
+    let reach = particles.iter().fold(0.0, |max: f64, particle| {
+        max.max(2.0 * particle.radius + (particle.dx.abs() + particle.dy.abs()))
+    });
+    let mut pairs = pairs_within(particles, container, bounds, reach);
+    pairs.retain(|&(i, j)| particles[i].collides_with(&particles[j]));
+    pairs
+}
+
+/// Every pair of particles whose centers may lie within `reach` of each
+/// other, plus some further apart, from a uniform grid with cells of that size.
+pub fn pairs_within(
+    particles: &[Particle],
+    container: &Container,
+    bounds: &Rectangle,
+    reach: f64,
+) -> Vec<(usize, usize)> {
+    if particles.len() < 2 || reach <= 0.0 {
+        return Vec::new();
+    }
+
+    let cell_size = reach;
+    let cells_x = ((bounds.width() / cell_size).floor() as i64).max(1);
+    let cells_y = ((bounds.height() / cell_size).floor() as i64).max(1);
+    let periodic_x = container.is_periodic_x();
+    let periodic_y = container.is_periodic_y();
+
+    let cell_of = |particle: &Particle| -> (i64, i64) {
+        (
+            ((particle.x - bounds.min_x()) / cell_size).floor() as i64,
+            ((particle.y - bounds.min_y()) / cell_size).floor() as i64,
+        )
+    };
+    let wrap = |cell: (i64, i64)| -> (i64, i64) {
+        (
+            if periodic_x {
+                cell.0.rem_euclid(cells_x)
+            } else {
+                cell.0
+            },
+            if periodic_y {
+                cell.1.rem_euclid(cells_y)
+            } else {
+                cell.1
+            },
+        )
+    };
+
+    let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, particle) in particles.iter().enumerate() {
+        grid.entry(wrap(cell_of(particle))).or_default().push(i);
+    }
+
+    let mut pairs = Vec::new();
+    for (i, particle) in particles.iter().enumerate() {
+        let (cx, cy) = cell_of(particle);
+        for ox in -1..=1 {
+            for oy in -1..=1 {
+                if let Some(others) = grid.get(&wrap((cx + ox, cy + oy))) {
+                    pairs.extend(others.iter().filter(|&&j| j > i).map(|&j| (i, j)));
+                }
+            }
+        }
+    }
+
+    // Small periodic grids can reach the same cell through several offsets.
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
+
+/// Merges every pair of touching particles into the heavier of the two,
+/// conserving mass and momentum and giving the result the combined area.
+/// Returns the indices of the absorbed particles in ascending order.
+pub fn merge_touching_particles(
+    particles: &mut [Particle],
+    container: &Container,
+    bounds: &Rectangle,
+) -> Vec<usize> {
+    let mut is_merged = vec![false; particles.len()];
+
+    for (i, j) in candidate_pairs(particles, container, bounds) {
+        if is_merged[i] || is_merged[j] {
+            continue;
+        }
+
+        let (dx, dy) = container.displacement(
+            (particles[j].x, particles[j].y),
+            (particles[i].x, particles[i].y),
+            bounds,
+        );
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance > particles[i].radius + particles[j].radius + CONTACT_SLOP {
+            continue;
+        }
+
+        let (survivor, absorbed) = if particles[i].mass >= particles[j].mass {
+            (i, j)
+        } else {
+            (j, i)
+        };
+        let (dx, dy) = if survivor == i { (dx, dy) } else { (-dx, -dy) };
+
+        let other = particles[absorbed];
+        let particle = &mut particles[survivor];
+        let mass = particle.mass + other.mass;
+        let share = other.mass / mass;
+
+        particle.x += dx * share;
+        particle.y += dy * share;
+        particle.dx = (particle.mass * particle.dx + other.mass * other.dx) / mass;
+        particle.dy = (particle.mass * particle.dy + other.mass * other.dy) / mass;
+        particle.radius = (particle.radius.powi(2) + other.radius.powi(2)).sqrt();
+        particle.mass = mass;
+        particle.charge += other.charge;
+        particle.lifetime = particle.lifetime.max(other.lifetime);
+
+        is_merged[absorbed] = true;
+    }
+
+    is_merged
+        .iter()
+        .enumerate()
+        .filter(|(_, merged)| **merged)
+        .map(|(i, _)| i)
+        .collect()
+}
@@ -0,0 +1,132 @@
+use crate::{
+    boundary::Container,
+    particle::{remapped_index, Particle},
+    rectangle::Rectangle,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum ConstraintKind {
+    /// Keeps the two particles exactly `length` apart.
+    Rigid,
+    /// Hooke spring with rest length `length` and damping along the link.
+    Spring { stiffness: f64, damping: f64 },
+    /// Slack below `length`, rigid once stretched to it.
+    Rope,
+}
+
+/// Link between the particles at indices `a` and `b`.
+#[derive(Clone, Copy, Debug)]
+pub struct Constraint {
+    pub id: u32,
+    pub a: usize,
+    pub b: usize,
+    pub length: f64,
+    pub kind: ConstraintKind,
+}
+
+impl Constraint {
+    fn solve(&self, particles: &mut [Particle], container: &Container, bounds: &Rectangle) {
+        if self.a == self.b || self.a >= particles.len() || self.b >= particles.len() {
+            return;
+        }
+
+        let (first, second) = if self.a < self.b {
+            let (head, tail) = particles.split_at_mut(self.b);
+            (&mut head[self.a], &mut tail[0])
+        } else {
+            let (head, tail) = particles.split_at_mut(self.a);
+            (&mut tail[0], &mut head[self.b])
+        };
+
+        let (dx, dy) = container.displacement((second.x, second.y), (first.x, first.y), bounds);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance == 0.0 {
+            return;
+        }
+
+        let (nx, ny) = (dx / distance, dy / distance);
+        let inverse_mass_a = 1.0 / first.mass;
+        let inverse_mass_b = 1.0 / second.mass;
+        let total_inverse_mass = inverse_mass_a + inverse_mass_b;
+        let relative_speed = (second.dx - first.dx) * nx + (second.dy - first.dy) * ny;
+
+        match self.kind {
+            ConstraintKind::Spring { stiffness, damping } => {
+                let force = stiffness * (distance - self.length) + damping * relative_speed;
+                first.dx += force * nx * inverse_mass_a;
+                first.dy += force * ny * inverse_mass_a;
+                second.dx -= force * nx * inverse_mass_b;
+                second.dy -= force * ny * inverse_mass_b;
+            }
+            ConstraintKind::Rigid | ConstraintKind::Rope => {
+                let error = distance - self.length;
+                if matches!(self.kind, ConstraintKind::Rope) && error <= 0.0 {
+                    return;
+                }
+
+                let correction = error / total_inverse_mass;
+                first.x += nx * correction * inverse_mass_a;
+                first.y += ny * correction * inverse_mass_a;
+                second.x -= nx * correction * inverse_mass_b;
+                second.y -= ny * correction * inverse_mass_b;
+
+                // Drop the relative velocity along the link, unless a rope is
+                // already going slack again.
+                if matches!(self.kind, ConstraintKind::Rope) && relative_speed < 0.0 {
+                    return;
+                }
+                let impulse = relative_speed / total_inverse_mass;
+                first.dx += impulse * nx * inverse_mass_a;
+                first.dy += impulse * ny * inverse_mass_a;
+                second.dx -= impulse * nx * inverse_mass_b;
+                second.dy -= impulse * ny * inverse_mass_b;
+            }
+        }
+    }
+}
+
+/// Relaxes every constraint `iterations` times. Springs only act once per
+/// tick so that their stiffness does not depend on the iteration count.
+pub fn solve_constraints(
+    constraints: &[Constraint],
+    particles: &mut [Particle],
+    container: &Container,
+    bounds: &Rectangle,
+    iterations: u32,
+) {
+    for constraint in constraints.iter() {
+        if matches!(constraint.kind, ConstraintKind::Spring { .. }) {
+            constraint.solve(particles, container, bounds);
+        }
+    }
+
+    for _ in 0..iterations {
+        for constraint in constraints.iter() {
+            if !matches!(constraint.kind, ConstraintKind::Spring { .. }) {
+                constraint.solve(particles, container, bounds);
+            }
+        }
+    }
+}
+
+/// Follows the particles after those at the sorted `removed` indices were
+/// deleted, dropping links that lost an end.
+pub fn remap_constraints(constraints: &mut Vec<Constraint>, removed: &[usize]) {
+    if removed.is_empty() {
+        return;
+    }
+
+    constraints.retain_mut(|constraint| {
+        match (
+            remapped_index(constraint.a, removed),
+            remapped_index(constraint.b, removed),
+        ) {
+            (Some(a), Some(b)) => {
+                constraint.a = a;
+                constraint.b = b;
+                true
+            }
+            _ => false,
+        }
+    });
+}
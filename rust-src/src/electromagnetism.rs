@@ -0,0 +1,319 @@
+use crate::{
+    boundary::Container,
+    collision::pairs_within,
+    geometry::{rotate, Vec2},
+    particle::Particle,
+    quad_tree::{ChargeCluster, QuadTree},
+    rectangle::Rectangle,
+};
+
+/// Coulomb interaction between charged particles and a uniform magnetic
+/// field pointing out of the screen.
+#[derive(Clone, Copy, Debug)]
+pub struct Electromagnetism {
+    /// Strength of the Coulomb force; like charges repel for positive values.
+    pub coulomb_constant: f64,
+    /// Keeps the force finite when two charges come close.
+    pub softening: f64,
+    /// Charges further apart than this do not interact; zero means no cutoff.
+    pub cutoff: f64,
+    /// Tree cells smaller than this fraction of their distance are treated as
+    /// a single charge of each sign.
+    pub opening_angle: f64,
+    /// Out-of-plane magnetic field; positive values turn positive charges
+    /// clockwise.
+    pub magnetic_field: f64,
+}
+
+impl Default for Electromagnetism {
+    fn default() -> Electromagnetism {
+        Electromagnetism {
+            coulomb_constant: 1.0,
+            softening: 2.0,
+            cutoff: 0.0,
+            opening_angle: 0.5,
+            magnetic_field: 0.0,
+        }
+    }
+}
+
+impl Electromagnetism {
+    /// Field per unit charge at `point` from `charge` at `source`. A source
+    /// right at `point` has no direction to push in, such as a particle's
+    /// own entry in the tree, and contributes nothing.
+    fn field(
+        &self,
+        point: Vec2,
+        source: Vec2,
+        charge: f64,
+        container: &Container,
+        bounds: &Rectangle,
+    ) -> Vec2 {
+        let (dx, dy) = container.displacement(point, source, bounds);
+        let distance_squared = dx * dx + dy * dy;
+        if distance_squared == 0.0 {
+            return (0.0, 0.0);
+        }
+        if self.cutoff > 0.0 && distance_squared > self.cutoff * self.cutoff {
+            return (0.0, 0.0);
+        }
+
+        let scale = self.coulomb_constant * charge
+            / (distance_squared + self.softening * self.softening).powf(1.5);
+        (scale * dx, scale * dy)
+    }
+
+    /// Field at `point` from every charge in `tree`, opening only the cells
+    /// that are too close to stand in for their contents.
+    fn tree_field(
+        &self,
+        tree: &QuadTree,
+        point: Vec2,
+        container: &Container,
+        bounds: &Rectangle,
+    ) -> Vec2 {
+        if tree.charges.iter().all(|cluster| cluster.charge == 0.0) {
+            return (0.0, 0.0);
+        }
+
+        let (dx, dy) = container.displacement(point, (tree.boundary.x, tree.boundary.y), bounds);
+        let size = tree.boundary.width().max(tree.boundary.height());
+        if size < self.opening_angle * (dx * dx + dy * dy).sqrt() {
+            return tree
+                .charges
+                .iter()
+                .fold((0.0, 0.0), |total, cluster: &ChargeCluster| {
+                    let field = self.field(
+                        point,
+                        (cluster.x, cluster.y),
+                        cluster.charge,
+                        container,
+                        bounds,
+                    );
+                    (total.0 + field.0, total.1 + field.1)
+                });
+        }
+
+        let mut total = tree.particles.iter().fold((0.0, 0.0), |total, particle| {
+            let field = self.field(
+                point,
+                (particle.x, particle.y),
+                particle.charge,
+                container,
+                bounds,
+            );
+            (total.0 + field.0, total.1 + field.1)
+        });
+
+        for child in [
+            &tree.northeast,
+            &tree.northwest,
+            &tree.southeast,
+            &tree.southwest,
+        ]
+        .iter()
+        .filter_map(|child| child.as_ref())
+        {
+            let field = self.tree_field(child, point, container, bounds);
+            total.0 += field.0;
+            total.1 += field.1;
+        }
+        total
+    }
+
+    /// Applies the Coulomb force between every pair of charged particles, or
+    /// only those within the cutoff, for one tick.
+    pub fn apply_coulomb_pairwise(
+        &self,
+        particles: &mut [Particle],
+        container: &Container,
+        bounds: &Rectangle,
+    ) {
+        if self.coulomb_constant == 0.0 || particles.iter().all(|p| p.charge == 0.0) {
+            return;
+        }
+
+        let pairs: Vec<(usize, usize)> = if self.cutoff > 0.0 {
+            pairs_within(particles, container, bounds, self.cutoff)
+        } else {
+            (0..particles.len())
+                .flat_map(|i| (i + 1..particles.len()).map(move |j| (i, j)))
+                .collect()
+        };
+
+        for (i, j) in pairs {
+            let (a, b) = (particles[i], particles[j]);
+            if a.charge == 0.0 || b.charge == 0.0 {
+                continue;
+            }
+
+            // Force on `a` from `b`; `b` feels the opposite one.
+            let field = self.field((a.x, a.y), (b.x, b.y), b.charge, container, bounds);
+            let force = (a.charge * field.0, a.charge * field.1);
+            particles[i].dx += force.0 / a.mass;
+            particles[i].dy += force.1 / a.mass;
+            particles[j].dx -= force.0 / b.mass;
+            particles[j].dy -= force.1 / b.mass;
+        }
+    }
+
+    /// Applies the Coulomb force for one tick, approximating distant groups
+    /// of charges Barnes-Hut style. Falls back to the pairwise sum when a
+    /// cutoff is set, since it already keeps the work local.
+    pub fn apply_coulomb_tree(
+        &self,
+        particles: &mut [Particle],
+        container: &Container,
+        bounds: &Rectangle,
+    ) {
+        if self.cutoff > 0.0 {
+            return self.apply_coulomb_pairwise(particles, container, bounds);
+        }
+        if self.coulomb_constant == 0.0 || particles.iter().all(|p| p.charge == 0.0) {
+            return;
+        }
+
+        let max_radius = particles
+            .iter()
+            .fold(0.0, |max: f64, particle| max.max(particle.radius));
+        let mut tree = QuadTree::new(container.bounding_box(bounds).expand(max_radius), 4);
+        for particle in particles.iter().filter(|particle| particle.charge != 0.0) {
+            let _ = tree.insert(particle);
+        }
+        tree.accumulate_charges();
+
+        for particle in particles.iter_mut() {
+            if particle.charge == 0.0 {
+                continue;
+            }
+
+            let field = self.tree_field(&tree, (particle.x, particle.y), container, bounds);
+            particle.dx += particle.charge * field.0 / particle.mass;
+            particle.dy += particle.charge * field.1 / particle.mass;
+        }
+    }
+
+    /// Turns the velocity of every charged particle by the Lorentz force for
+    /// one tick. Rotating rather than adding `q v × B / m` keeps the speed,
+    /// so orbits neither spiral out nor decay.
+    pub fn apply_magnetic_field(&self, particles: &mut [Particle]) {
+        if self.magnetic_field == 0.0 {
+            return;
+        }
+
+        for particle in particles.iter_mut() {
+            let angle = -particle.charge * self.magnetic_field / particle.mass;
+            let (dx, dy) = rotate((particle.dx, particle.dy), angle);
+            particle.dx = dx;
+            particle.dy = dy;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collision::merge_touching_particles,
+        fragment::{fragment_impacting_particles, Fragmentation},
+        particle::remove_particles,
+    };
+
+    fn charged(x: f64, y: f64, charge: f64) -> Particle {
+        let mut particle = Particle::new(x, y, 0.0, 0.0, 1.0);
+        particle.charge = charge;
+        particle
+    }
+
+    /// Total charge, mass and momentum.
+    fn totals(particles: &[Particle]) -> (f64, f64, f64, f64) {
+        particles
+            .iter()
+            .fold((0.0, 0.0, 0.0, 0.0), |sum, particle| {
+                (
+                    sum.0 + particle.charge,
+                    sum.1 + particle.mass,
+                    sum.2 + particle.mass * particle.dx,
+                    sum.3 + particle.mass * particle.dy,
+                )
+            })
+    }
+
+    fn assert_conserved(before: (f64, f64, f64, f64), after: (f64, f64, f64, f64)) {
+        for (before, after) in [
+            (before.0, after.0),
+            (before.1, after.1),
+            (before.2, after.2),
+            (before.3, after.3),
+        ] {
+            assert!((before - after).abs() < 1e-9, "{} became {}", before, after);
+        }
+    }
+
+    fn head_on_pair(charges: (f64, f64), speed: f64) -> Vec<Particle> {
+        let mut first = Particle::new(40.0, 50.0, speed, 0.5, 8.0);
+        first.charge = charges.0;
+        let mut second = Particle::new(56.5, 50.0, -speed, 0.0, 8.0);
+        second.charge = charges.1;
+        vec![first, second]
+    }
+
+    #[test]
+    fn fragments_share_the_parent_charge() {
+        let container = Container::rectangle();
+        let bounds = Rectangle::from_corners(0.0, 0.0, 100.0, 100.0);
+        let settings = Fragmentation {
+            threshold: 1.0,
+            ..Fragmentation::default()
+        };
+        let mut particles = head_on_pair((1.0, -3.0), 4.0);
+        let before = totals(&particles);
+
+        let shattered =
+            fragment_impacting_particles(&mut particles, &container, &bounds, &settings);
+        assert_eq!(shattered, [0, 1]);
+        remove_particles(&mut particles, &shattered);
+
+        assert_eq!(particles.len(), 2 * settings.pieces as usize);
+        assert_conserved(before, totals(&particles));
+    }
+
+    #[test]
+    fn merged_particles_add_their_charges() {
+        let container = Container::rectangle();
+        let bounds = Rectangle::from_corners(0.0, 0.0, 100.0, 100.0);
+        let mut particles = head_on_pair((1.0, 2.0), 1.0);
+        particles[1].x = 55.0;
+        let before = totals(&particles);
+
+        let merged = merge_touching_particles(&mut particles, &container, &bounds);
+        assert_eq!(merged.len(), 1);
+        remove_particles(&mut particles, &merged);
+
+        assert_eq!(particles[0].charge, 3.0);
+        assert_conserved(before, totals(&particles));
+    }
+
+    #[test]
+    fn unsoftened_coulomb_stays_finite() {
+        let electromagnetism = Electromagnetism {
+            softening: 0.0,
+            ..Electromagnetism::default()
+        };
+        let container = Container::rectangle();
+        let bounds = Rectangle::from_corners(0.0, 0.0, 100.0, 100.0);
+        let mut particles = vec![
+            charged(20.0, 20.0, 1.0),
+            charged(20.0, 20.0, 2.0),
+            charged(60.0, 40.0, 1.0),
+        ];
+
+        electromagnetism.apply_coulomb_pairwise(&mut particles, &container, &bounds);
+        electromagnetism.apply_coulomb_tree(&mut particles, &container, &bounds);
+
+        for particle in particles.iter() {
+            assert!(particle.dx.is_finite() && particle.dy.is_finite());
+        }
+        assert!(particles[2].dx != 0.0);
+    }
+}
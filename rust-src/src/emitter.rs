@@ -0,0 +1,132 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{particle::Particle, utils::get_random_in_range};
+
+/// Spawns particles from a point, or uniformly along the segment from
+/// `(x1, y1)` to `(x2, y2)`, at a fixed rate while it is active.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Emitter {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+    /// Particles spawned per tick; fractional rates accumulate across ticks.
+    pub rate: f64,
+    /// Central emission direction in radians.
+    pub direction: f64,
+    /// Half-angle of the emission cone in radians.
+    pub spread: f64,
+    pub min_speed: f64,
+    pub max_speed: f64,
+    pub min_radius: f64,
+    pub max_radius: f64,
+    /// Total number of particles the emitter may spawn; zero means unlimited.
+    pub max_count: u32,
+    /// Ticks each spawned particle lives for; zero or less means forever.
+    pub lifetime: f64,
+    pub is_active: bool,
+    id: u32,
+    emitted: u32,
+    accumulator: f64,
+}
+
+#[wasm_bindgen]
+impl Emitter {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: f64, y: f64) -> Emitter {
+        Emitter {
+            x1: x,
+            y1: y,
+            x2: x,
+            y2: y,
+            rate: 1.0,
+            direction: 0.0,
+            spread: 0.0,
+            min_speed: 1.0,
+            max_speed: 1.0,
+            min_radius: 2.0,
+            max_radius: 2.0,
+            max_count: 0,
+            lifetime: 0.0,
+            is_active: true,
+            id: 0,
+            emitted: 0,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Turns a point emitter into a line source ending at `(x2, y2)`.
+    #[wasm_bindgen]
+    pub fn set_segment_end(&mut self, x2: f64, y2: f64) {
+        self.x2 = x2;
+        self.y2 = y2;
+    }
+
+    /// Handle given out by the universe the emitter was added to.
+    #[wasm_bindgen]
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    #[wasm_bindgen]
+    pub fn get_emitted_count(&self) -> u32 {
+        self.emitted
+    }
+}
+
+impl Emitter {
+    pub fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+
+    pub fn set_emitted_count(&mut self, emitted: u32) {
+        self.emitted = emitted;
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.max_count > 0 && self.emitted >= self.max_count
+    }
+
+    /// Spawns this tick's share of particles into `particles`.
+    pub fn emit(&mut self, particles: &mut Vec<Particle>) {
+        if !self.is_active || self.is_exhausted() {
+            return;
+        }
+
+        self.accumulator += self.rate;
+        while self.accumulator >= 1.0 && !self.is_exhausted() {
+            self.accumulator -= 1.0;
+
+            let t = get_random_in_range(0.0, 1.0);
+            let x = self.x1 + t * (self.x2 - self.x1);
+            let y = self.y1 + t * (self.y2 - self.y1);
+
+            let angle = self.direction + get_random_in_range(-self.spread, self.spread);
+            let speed = get_random_in_range(self.min_speed, self.max_speed);
+            let radius = get_random_in_range(self.min_radius, self.max_radius);
+
+            let mut particle =
+                Particle::new(x, y, speed * angle.cos(), speed * angle.sin(), radius);
+            if self.lifetime > 0.0 {
+                particle.lifetime = self.lifetime;
+            }
+
+            particles.push(particle);
+            self.emitted += 1;
+        }
+    }
+}
+
+/// Counts down particle lifetimes and returns the indices of the particles
+/// that ran out, in ascending order.
+pub fn age_particles(particles: &mut [Particle]) -> Vec<usize> {
+    let mut expired = Vec::new();
+    for (i, particle) in particles.iter_mut().enumerate() {
+        particle.lifetime -= 1.0;
+        if particle.lifetime <= 0.0 {
+            expired.push(i);
+        }
+    }
+    expired
+}
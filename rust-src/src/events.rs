@@ -0,0 +1,89 @@
+use crate::{
+    geometry::{dot, Vec2},
+    particle::Particle,
+};
+
+/// `other_id` of a collision with the container walls.
+pub const WALL_ID: f64 = -1.0;
+
+/// One collision resolved during a tick, between the particles `id` and
+/// `other_id` or between `id` and the walls.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct CollisionEvent {
+    pub id: f64,
+    /// Id of the particle it hit, or [`WALL_ID`].
+    pub other_id: f64,
+    /// Point on the surface of particle `id` where it was hit.
+    pub x: f64,
+    pub y: f64,
+    /// Unit normal pointing from what it hit towards particle `id`.
+    pub normal_x: f64,
+    pub normal_y: f64,
+    /// Speed at which the two were closing along the normal.
+    pub relative_speed: f64,
+    /// Momentum particle `id` received along the normal; the other particle
+    /// received the opposite.
+    pub impulse: f64,
+}
+
+impl CollisionEvent {
+    pub fn new(
+        particle: &Particle,
+        other_id: f64,
+        normal: Vec2,
+        relative_speed: f64,
+        impulse: f64,
+    ) -> CollisionEvent {
+        CollisionEvent {
+            id: particle.id,
+            other_id,
+            x: particle.x - normal.0 * particle.radius,
+            y: particle.y - normal.1 * particle.radius,
+            normal_x: normal.0,
+            normal_y: normal.1,
+            relative_speed,
+            impulse,
+        }
+    }
+
+    /// Collision that changed the velocities of `particle` and `other` from
+    /// `velocities`, with `normal` pointing from `other` towards `particle`.
+    pub fn between(
+        particle: &Particle,
+        other: &Particle,
+        velocities: (Vec2, Vec2),
+        normal: Vec2,
+    ) -> CollisionEvent {
+        let (velocity, other_velocity) = velocities;
+        let closing = (other_velocity.0 - velocity.0, other_velocity.1 - velocity.1);
+        let change = (particle.dx - velocity.0, particle.dy - velocity.1);
+
+        CollisionEvent::new(
+            particle,
+            other.id,
+            normal,
+            dot(closing, normal),
+            particle.mass * dot(change, normal),
+        )
+    }
+
+    /// Wall bounce that changed the velocity of `particle` from `velocity`,
+    /// or nothing if it did not bounce.
+    pub fn off_wall(particle: &Particle, velocity: Vec2) -> Option<CollisionEvent> {
+        let change = (particle.dx - velocity.0, particle.dy - velocity.1);
+        let magnitude = (change.0 * change.0 + change.1 * change.1).sqrt();
+        if magnitude == 0.0 {
+            return None;
+        }
+
+        let normal = (change.0 / magnitude, change.1 / magnitude);
+        Some(CollisionEvent::new(
+            particle,
+            WALL_ID,
+            normal,
+            -dot(velocity, normal),
+            particle.mass * magnitude,
+        ))
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{geometry::Vec2, particle::Particle, polygon::Polygon, rectangle::Rectangle};
+
+#[derive(Clone, Copy, Debug)]
+pub enum ForceKind {
+    /// Constant acceleration, independent of mass.
+    Gravity { gx: f64, gy: f64 },
+    /// Force `-coefficient * v`.
+    LinearDrag { coefficient: f64 },
+    /// Force `-coefficient * |v| * v`.
+    QuadraticDrag { coefficient: f64 },
+    /// Linear drag relative to the moving air, pulling velocities towards `(vx, vy)`.
+    Wind { vx: f64, vy: f64, coefficient: f64 },
+    /// Acceleration towards `(x, y)` of `strength / distance^falloff`; a
+    /// negative strength repels. `softening` keeps it finite at the center.
+    Point {
+        x: f64,
+        y: f64,
+        strength: f64,
+        falloff: f64,
+        softening: f64,
+    },
+    /// Counter-clockwise swirl around `(x, y)` of `strength / distance^falloff`;
+    /// a negative strength turns clockwise.
+    Vortex {
+        x: f64,
+        y: f64,
+        strength: f64,
+        falloff: f64,
+        softening: f64,
+    },
+}
+
+/// A force acting on every body inside `region`, or everywhere when unset.
+#[derive(Clone, Copy, Debug)]
+pub struct ForceField {
+    pub id: u32,
+    pub kind: ForceKind,
+    pub region: Option<Rectangle>,
+}
+
+impl ForceField {
+    pub fn new(id: u32, kind: ForceKind) -> ForceField {
+        ForceField {
+            id,
+            kind,
+            region: None,
+        }
+    }
+
+    /// Change in velocity over one tick for a body of `mass` at `position`
+    /// moving with `velocity`.
+    pub fn acceleration(&self, position: Vec2, velocity: Vec2, mass: f64) -> Vec2 {
+        if let Some(region) = &self.region {
+            if !region.contains_point(position.0, position.1) {
+                return (0.0, 0.0);
+            }
+        }
+
+        match self.kind {
+            ForceKind::Gravity { gx, gy } => (gx, gy),
+            ForceKind::LinearDrag { coefficient } => (
+                -coefficient * velocity.0 / mass,
+                -coefficient * velocity.1 / mass,
+            ),
+            ForceKind::QuadraticDrag { coefficient } => {
+                let speed = (velocity.0 * velocity.0 + velocity.1 * velocity.1).sqrt();
+                (
+                    -coefficient * speed * velocity.0 / mass,
+                    -coefficient * speed * velocity.1 / mass,
+                )
+            }
+            ForceKind::Wind {
+                vx,
+                vy,
+                coefficient,
+            } => (
+                coefficient * (vx - velocity.0) / mass,
+                coefficient * (vy - velocity.1) / mass,
+            ),
+            ForceKind::Point {
+                x,
+                y,
+                strength,
+                falloff,
+                softening,
+            } => {
+                let (dx, dy) = (x - position.0, y - position.1);
+                let distance = (dx * dx + dy * dy + softening * softening).sqrt();
+                let magnitude = strength / distance.powf(falloff);
+                (magnitude * dx / distance, magnitude * dy / distance)
+            }
+            ForceKind::Vortex {
+                x,
+                y,
+                strength,
+                falloff,
+                softening,
+            } => {
+                let (dx, dy) = (position.0 - x, position.1 - y);
+                let distance = (dx * dx + dy * dy + softening * softening).sqrt();
+                let magnitude = strength / distance.powf(falloff);
+                (-magnitude * dy / distance, magnitude * dx / distance)
+            }
+        }
+    }
+}
+
+/// Applies every field to the particles and polygons for one tick.
+pub fn apply_force_fields(
+    fields: &[ForceField],
+    particles: &mut [Particle],
+    polygons: &mut [Polygon],
+) {
+    if fields.is_empty() {
+        return;
+    }
+
+    for particle in particles.iter_mut() {
+        for field in fields.iter() {
+            let (ax, ay) = field.acceleration(
+                (particle.x, particle.y),
+                (particle.dx, particle.dy),
+                particle.mass,
+            );
+            particle.dx += ax;
+            particle.dy += ay;
+        }
+    }
+
+    for polygon in polygons.iter_mut() {
+        for field in fields.iter() {
+            let (ax, ay) = field.acceleration(
+                (polygon.x, polygon.y),
+                (polygon.dx, polygon.dy),
+                polygon.mass,
+            );
+            polygon.dx += ax;
+            polygon.dy += ay;
+        }
+    }
+}
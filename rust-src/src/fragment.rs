@@ -0,0 +1,201 @@
+use std::f64::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    boundary::Container, collision::candidate_pairs, geometry::dot, particle::Particle,
+    rectangle::Rectangle, utils::get_random_in_range,
+};
+
+/// How the speeds of fragments flying away from an impact are spread.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScatterDistribution {
+    /// Every fragment flies straight out from the center at the same speed.
+    Even = 0,
+    /// Jittered directions with speeds uniform between zero and twice the mean.
+    Uniform = 1,
+    /// Jittered directions with exponentially distributed speeds, so a few
+    /// shards fly off much faster than the rest.
+    Exponential = 2,
+}
+
+/// When and how particles break apart on impact.
+#[derive(Clone, Copy, Debug)]
+pub struct Fragmentation {
+    /// Impact energy, in the frame of the pair's center of mass, above which
+    /// both particles shatter.
+    pub threshold: f64,
+    /// Number of fragments each shattered particle breaks into.
+    pub pieces: u32,
+    /// Particles whose fragments would be smaller than this stay whole.
+    pub min_radius: f64,
+    /// Fraction of the impact energy that goes into scattering the fragments;
+    /// the rest is lost.
+    pub scatter: f64,
+    pub distribution: ScatterDistribution,
+}
+
+impl Default for Fragmentation {
+    fn default() -> Fragmentation {
+        Fragmentation {
+            threshold: 10.0,
+            pieces: 4,
+            min_radius: 1.0,
+            scatter: 0.5,
+            distribution: ScatterDistribution::Even,
+        }
+    }
+}
+
+impl Fragmentation {
+    fn can_shatter(&self, particle: &Particle) -> bool {
+        self.pieces >= 2 && particle.radius / (self.pieces as f64).sqrt() >= self.min_radius
+    }
+
+    /// Replaces `parent` with fragments of equal mass and charge and the same
+    /// total area, laid out on a ring facing `angle` and scattered at
+    /// `mean_speed` on top of the parent's velocity.
+    fn shatter(&self, parent: &Particle, angle: f64, mean_speed: f64) -> Vec<Particle> {
+        let pieces = self.pieces as usize;
+        let radius = parent.radius / (pieces as f64).sqrt();
+        // Far enough out that neighbouring fragments do not overlap.
+        let ring = radius / (PI / pieces as f64).sin();
+        let jitter = PI / pieces as f64;
+
+        let mut scatter: Vec<(f64, f64)> = (0..pieces)
+            .map(|k| {
+                let direction = angle + 2.0 * PI * k as f64 / pieces as f64;
+                let (direction, speed) = match self.distribution {
+                    ScatterDistribution::Even => (direction, mean_speed),
+                    ScatterDistribution::Uniform => (
+                        direction + get_random_in_range(-jitter, jitter),
+                        get_random_in_range(0.0, 2.0 * mean_speed),
+                    ),
+                    ScatterDistribution::Exponential => (
+                        direction + get_random_in_range(-jitter, jitter),
+                        -mean_speed * (1.0 - get_random_in_range(0.0, 1.0)).ln(),
+                    ),
+                };
+                (speed * direction.cos(), speed * direction.sin())
+            })
+            .collect();
+
+        // Random speeds do not cancel out on their own; removing their mean
+        // keeps the parent's momentum intact.
+        let mean = scatter
+            .iter()
+            .fold((0.0, 0.0), |sum, v| (sum.0 + v.0, sum.1 + v.1));
+        let mean = (mean.0 / pieces as f64, mean.1 / pieces as f64);
+        for velocity in scatter.iter_mut() {
+            velocity.0 -= mean.0;
+            velocity.1 -= mean.1;
+        }
+
+        scatter
+            .iter()
+            .enumerate()
+            .map(|(k, velocity)| {
+                let direction = angle + 2.0 * PI * k as f64 / pieces as f64;
+                let mut fragment = *parent;
+                fragment.x += ring * direction.cos();
+                fragment.y += ring * direction.sin();
+                fragment.dx += velocity.0;
+                fragment.dy += velocity.1;
+                fragment.radius = radius;
+                fragment.mass = parent.mass / pieces as f64;
+                fragment.charge = parent.charge / pieces as f64;
+                fragment
+            })
+            .collect()
+    }
+}
+
+/// Shatters every pair of particles that will hit each other this tick with
+/// more than the threshold energy. The pair first loses its approach speed
+/// as in a perfectly inelastic impact, then each particle big enough to
+/// break is replaced by fragments appended to `particles`. Returns the
+/// indices of the shattered particles in ascending order.
+pub fn fragment_impacting_particles(
+    particles: &mut Vec<Particle>,
+    container: &Container,
+    bounds: &Rectangle,
+    settings: &Fragmentation,
+) -> Vec<usize> {
+    let mut is_shattered = vec![false; particles.len()];
+    let mut fragments = Vec::new();
+
+    for (i, j) in candidate_pairs(particles, container, bounds) {
+        if is_shattered[i] || is_shattered[j] {
+            continue;
+        }
+
+        let (a, b) = (particles[i], particles[j]);
+        let (dx, dy) =
+            container.displacement((b.x + b.dx, b.y + b.dy), (a.x + a.dx, a.y + a.dy), bounds);
+        if (dx * dx + dy * dy).sqrt() >= a.radius + b.radius {
+            continue;
+        }
+
+        // Normal from `a` towards `b` before the move, since fast pairs may
+        // already have passed each other by the end of it. Positive closing
+        // speed means approach.
+        let (dx, dy) = container.displacement((b.x, b.y), (a.x, a.y), bounds);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance == 0.0 {
+            continue;
+        }
+        let normal = (dx / distance, dy / distance);
+        let closing_speed = dot((a.dx - b.dx, a.dy - b.dy), normal);
+        if closing_speed <= 0.0 {
+            continue;
+        }
+
+        let reduced_mass = a.mass * b.mass / (a.mass + b.mass);
+        let energy = 0.5 * reduced_mass * closing_speed * closing_speed;
+        if energy <= settings.threshold {
+            continue;
+        }
+
+        let breaks = [settings.can_shatter(&a), settings.can_shatter(&b)];
+        if !breaks[0] && !breaks[1] {
+            continue;
+        }
+
+        let shared_speed = (a.mass * dot((a.dx, a.dy), normal)
+            + b.mass * dot((b.dx, b.dy), normal))
+            / (a.mass + b.mass);
+        let breaking_mass = [a, b]
+            .iter()
+            .zip(breaks.iter())
+            .filter(|(_, breaks)| **breaks)
+            .map(|(particle, _)| particle.mass)
+            .sum::<f64>();
+        let mean_speed = (2.0 * settings.scatter.max(0.0) * energy / breaking_mass).sqrt();
+        let impact_angle = normal.1.atan2(normal.0);
+
+        for (index, angle, shatters) in [
+            (i, impact_angle, breaks[0]),
+            (j, impact_angle + PI, breaks[1]),
+        ] {
+            let particle = &mut particles[index];
+            let normal_speed = dot((particle.dx, particle.dy), normal);
+            particle.dx += (shared_speed - normal_speed) * normal.0;
+            particle.dy += (shared_speed - normal_speed) * normal.1;
+
+            if shatters {
+                fragments.extend(settings.shatter(particle, angle, mean_speed));
+                is_shattered[index] = true;
+            }
+        }
+    }
+
+    particles.extend(fragments);
+
+    is_shattered
+        .iter()
+        .enumerate()
+        .filter(|(_, shattered)| **shattered)
+        .map(|(i, _)| i)
+        .collect()
+}
@@ -0,0 +1,135 @@
+pub type Vec2 = (f64, f64);
+
+pub fn add(a: Vec2, b: Vec2) -> Vec2 {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+pub fn sub(a: Vec2, b: Vec2) -> Vec2 {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+pub fn scale(a: Vec2, s: f64) -> Vec2 {
+    (a.0 * s, a.1 * s)
+}
+
+pub fn dot(a: Vec2, b: Vec2) -> f64 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+/// Z component of the 3D cross product of `a` and `b`.
+pub fn cross(a: Vec2, b: Vec2) -> f64 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+pub fn length(a: Vec2) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Unit vector along `a`, or the zero vector when `a` has no length.
+pub fn normalize(a: Vec2) -> Vec2 {
+    let len = length(a);
+    if len == 0.0 {
+        return (0.0, 0.0);
+    }
+    (a.0 / len, a.1 / len)
+}
+
+pub fn rotate(a: Vec2, angle: f64) -> Vec2 {
+    let (sin, cos) = angle.sin_cos();
+    (cos * a.0 - sin * a.1, sin * a.0 + cos * a.1)
+}
+
+pub fn closest_point_on_segment(p: Vec2, a: Vec2, b: Vec2) -> Vec2 {
+    let ab = sub(b, a);
+    let len_sq = dot(ab, ab);
+    if len_sq == 0.0 {
+        return a;
+    }
+    let t = (dot(sub(p, a), ab) / len_sq).clamp(0.0, 1.0);
+    add(a, scale(ab, t))
+}
+
+/// Signed area, positive when the vertices wind counter-clockwise in a y-up frame.
+pub fn polygon_area(vertices: &[Vec2]) -> f64 {
+    let n = vertices.len();
+    (0..n)
+        .map(|i| cross(vertices[i], vertices[(i + 1) % n]))
+        .sum::<f64>()
+        / 2.0
+}
+
+pub fn polygon_centroid(vertices: &[Vec2]) -> Vec2 {
+    let n = vertices.len();
+    let area = polygon_area(vertices);
+    if area == 0.0 {
+        let sum = vertices.iter().fold((0.0, 0.0), |acc, v| add(acc, *v));
+        return scale(sum, 1.0 / n as f64);
+    }
+
+    let mut cx = 0.0;
+    let mut cy = 0.0;
+    for i in 0..n {
+        let a = vertices[i];
+        let b = vertices[(i + 1) % n];
+        let c = cross(a, b);
+        cx += (a.0 + b.0) * c;
+        cy += (a.1 + b.1) * c;
+    }
+    (cx / (6.0 * area), cy / (6.0 * area))
+}
+
+/// Even-odd test, valid for concave polygons as well as convex ones.
+pub fn point_in_polygon(p: Vec2, vertices: &[Vec2]) -> bool {
+    let n = vertices.len();
+    let mut inside = false;
+    let mut j = n - 1;
+    for i in 0..n {
+        let (xi, yi) = vertices[i];
+        let (xj, yj) = vertices[j];
+        if (yi > p.1) != (yj > p.1) && p.0 < (xj - xi) * (p.1 - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Convex hull of `points` in counter-clockwise order (monotone chain).
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    sorted.dedup();
+
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let mut hull: Vec<Vec2> = Vec::with_capacity(sorted.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for &p in sorted.iter() {
+            while hull.len() >= start + 2
+                && cross(
+                    sub(hull[hull.len() - 1], hull[hull.len() - 2]),
+                    sub(p, hull[hull.len() - 2]),
+                ) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    hull
+}
+
+/// Interprets a flat `[x0, y0, x1, y1, ...]` buffer as a list of points.
+pub fn points_from_flat(coordinates: &[f64]) -> Vec<Vec2> {
+    coordinates
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect()
+}
@@ -8,9 +8,26 @@ extern "C" {
     fn log(s: &str);
 }
 
+pub mod boundary;
+pub mod collision;
+pub mod constraint;
+pub mod electromagnetism;
+pub mod emitter;
+pub mod events;
+pub mod force_field;
+pub mod fragment;
+pub mod geometry;
+pub mod observables;
+pub mod obstacle;
 pub mod particle;
+pub mod pbd;
+pub mod picking;
+pub mod pointer;
+pub mod polygon;
+pub mod potential;
 pub mod quad_tree;
 pub mod rectangle;
+pub mod thermostat;
 
 pub mod utils;
 
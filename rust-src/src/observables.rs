@@ -0,0 +1,507 @@
+use std::f64::consts::PI;
+
+use crate::{
+    boundary::Container,
+    collision::pairs_within,
+    geometry::Vec2,
+    particle::{remapped_index, Particle},
+    rectangle::Rectangle,
+};
+
+/// Most bins or lags an observable allocates, so sizes passed in from
+/// JavaScript cannot exhaust the wasm memory.
+pub const MAX_BINS: usize = 4096;
+
+/// Mean kinetic energy per particle, which equals `kT` for two degrees of
+/// freedom with Boltzmann's constant set to one.
+pub fn temperature(particles: &[Particle]) -> f64 {
+    if particles.is_empty() {
+        return 0.0;
+    }
+
+    let kinetic_energy: f64 = particles
+        .iter()
+        .map(|particle| 0.5 * particle.mass * (particle.dx.powi(2) + particle.dy.powi(2)))
+        .sum();
+    kinetic_energy / particles.len() as f64
+}
+
+/// Momentum handed to the container walls, averaged over the ticks since
+/// the last reset.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WallPressure {
+    impulse: f64,
+    ticks: u32,
+}
+
+impl WallPressure {
+    pub fn add_impulse(&mut self, impulse: f64) {
+        self.impulse += impulse;
+    }
+
+    /// Records the bounce that changed the velocity of `particle` from
+    /// `velocity`.
+    pub fn record(&mut self, particle: &Particle, velocity: Vec2) {
+        let (dx, dy) = (particle.dx - velocity.0, particle.dy - velocity.1);
+        self.impulse += particle.mass * (dx * dx + dy * dy).sqrt();
+    }
+
+    pub fn end_tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    /// Force per unit length of wall, the two-dimensional pressure.
+    pub fn pressure(&self, wall_length: f64) -> f64 {
+        if self.ticks == 0 || wall_length <= 0.0 {
+            return 0.0;
+        }
+        self.impulse / (self.ticks as f64 * wall_length)
+    }
+
+    pub fn reset(&mut self) {
+        *self = WallPressure::default();
+    }
+}
+
+/// Probability density of particle speeds in `bins` equal bins, at most
+/// [`MAX_BINS`], from zero to `max_speed`. Faster particles count towards the
+/// total but fall outside.
+pub fn speed_histogram(particles: &[Particle], bins: usize, max_speed: f64) -> Vec<f64> {
+    let bins = bins.min(MAX_BINS);
+    let mut histogram = vec![0.0; bins];
+    if bins == 0 || max_speed <= 0.0 || particles.is_empty() {
+        return histogram;
+    }
+
+    let width = max_speed / bins as f64;
+    for particle in particles.iter() {
+        let speed = (particle.dx.powi(2) + particle.dy.powi(2)).sqrt();
+        let bin = (speed / width) as usize;
+        if bin < bins {
+            histogram[bin] += 1.0;
+        }
+    }
+
+    let scale = 1.0 / (particles.len() as f64 * width);
+    for value in histogram.iter_mut() {
+        *value *= scale;
+    }
+    histogram
+}
+
+/// Maximum-likelihood fit of the two-dimensional Maxwell-Boltzmann speed
+/// distribution, treating every particle as having the mean mass. Returns
+/// the mean mass and the fitted temperature.
+pub fn fit_maxwell_boltzmann(particles: &[Particle]) -> (f64, f64) {
+    if particles.is_empty() {
+        return (0.0, 0.0);
+    }
+
+    let count = particles.len() as f64;
+    let mass = particles.iter().map(|particle| particle.mass).sum::<f64>() / count;
+    let mean_square_speed = particles
+        .iter()
+        .map(|particle| particle.dx.powi(2) + particle.dy.powi(2))
+        .sum::<f64>()
+        / count;
+    (mass, 0.5 * mass * mean_square_speed)
+}
+
+/// `f(v) = (m v / kT) exp(-m v² / 2kT)`, the speed density of an ideal gas
+/// in two dimensions.
+pub fn maxwell_boltzmann_density(speed: f64, mass: f64, temperature: f64) -> f64 {
+    if temperature <= 0.0 {
+        return 0.0;
+    }
+
+    let a = mass / temperature;
+    a * speed * (-0.5 * a * speed * speed).exp()
+}
+
+/// Fitted Maxwell-Boltzmann density at the centers of the bins used by
+/// [`speed_histogram`], for drawing over it.
+pub fn maxwell_boltzmann_curve(particles: &[Particle], bins: usize, max_speed: f64) -> Vec<f64> {
+    let bins = bins.min(MAX_BINS);
+    let (mass, temperature) = fit_maxwell_boltzmann(particles);
+    let width = max_speed / bins.max(1) as f64;
+    (0..bins)
+        .map(|bin| maxwell_boltzmann_density((bin as f64 + 0.5) * width, mass, temperature))
+        .collect()
+}
+
+/// Radial distribution function `g(r)`, averaged over every sampled tick.
+/// Near solid walls it dips below one at large `r`, since fewer neighbours
+/// fit inside the container there.
+#[derive(Clone, Debug)]
+pub struct RadialDistribution {
+    bin_width: f64,
+    /// Pair counts per bin, each sample already divided by `N * density`.
+    counts: Vec<f64>,
+    density: f64,
+    samples: u32,
+}
+
+impl RadialDistribution {
+    pub fn new(bin_width: f64, max_radius: f64) -> RadialDistribution {
+        let bins = if bin_width > 0.0 {
+            (max_radius / bin_width).ceil().max(0.0)
+        } else {
+            0.0
+        };
+        // Past the cap the bins widen instead, so the range stays the same.
+        let (bins, bin_width) = if bins > MAX_BINS as f64 {
+            (MAX_BINS, max_radius / MAX_BINS as f64)
+        } else {
+            (bins as usize, bin_width)
+        };
+        RadialDistribution {
+            bin_width,
+            counts: vec![0.0; bins],
+            density: 0.0,
+            samples: 0,
+        }
+    }
+
+    fn max_radius(&self) -> f64 {
+        self.bin_width * self.counts.len() as f64
+    }
+
+    /// Adds the current pair distances to the average.
+    pub fn sample(&mut self, particles: &[Particle], container: &Container, bounds: &Rectangle) {
+        let area = container.area(bounds);
+        if particles.len() < 2 || self.counts.is_empty() || area <= 0.0 {
+            return;
+        }
+
+        let density = particles.len() as f64 / area;
+        let weight = 2.0 / (particles.len() as f64 * density);
+        for (i, j) in pairs_within(particles, container, bounds, self.max_radius()) {
+            let (dx, dy) = container.displacement(
+                (particles[i].x, particles[i].y),
+                (particles[j].x, particles[j].y),
+                bounds,
+            );
+            let bin = ((dx * dx + dy * dy).sqrt() / self.bin_width) as usize;
+            if bin < self.counts.len() {
+                self.counts[bin] += weight;
+            }
+        }
+
+        self.density += density;
+        self.samples += 1;
+    }
+
+    /// `g(r)` at each bin, from `r = 0` up to the maximum radius.
+    pub fn values(&self) -> Vec<f64> {
+        if self.samples == 0 {
+            return vec![0.0; self.counts.len()];
+        }
+
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(bin, count)| {
+                let inner = bin as f64 * self.bin_width;
+                let outer = inner + self.bin_width;
+                let shell = PI * (outer * outer - inner * inner);
+                count / (self.samples as f64 * shell)
+            })
+            .collect()
+    }
+
+    /// Static structure factor `S(k)` at the centers of `bins` equal bins
+    /// from zero to `max_wavenumber`, from the two-dimensional Fourier
+    /// transform of `g(r) - 1` over the sampled range. Takes at most
+    /// [`MAX_BINS`] bins.
+    pub fn structure_factor(&self, max_wavenumber: f64, bins: usize) -> Vec<f64> {
+        let bins = bins.min(MAX_BINS);
+        if self.samples == 0 || bins == 0 {
+            return vec![0.0; bins];
+        }
+
+        let density = self.density / self.samples as f64;
+        let g = self.values();
+        let step = max_wavenumber / bins as f64;
+        (0..bins)
+            .map(|bin| {
+                let k = (bin as f64 + 0.5) * step;
+                let integral: f64 = g
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        let r = (i as f64 + 0.5) * self.bin_width;
+                        (value - 1.0) * bessel_j0(k * r) * 2.0 * PI * r * self.bin_width
+                    })
+                    .sum();
+                1.0 + density * integral
+            })
+            .collect()
+    }
+}
+
+/// Collision rate and free paths between collisions since the statistics
+/// were started, for comparison with kinetic theory.
+#[derive(Clone, Debug)]
+pub struct CollisionStatistics {
+    bin_width: f64,
+    free_path_counts: Vec<f64>,
+    free_path_total: f64,
+    collisions: f64,
+    particle_ticks: f64,
+}
+
+impl CollisionStatistics {
+    pub fn new(bins: usize, max_free_path: f64) -> CollisionStatistics {
+        let bins = bins.min(MAX_BINS);
+        CollisionStatistics {
+            bin_width: max_free_path / bins.max(1) as f64,
+            free_path_counts: vec![0.0; bins],
+            free_path_total: 0.0,
+            collisions: 0.0,
+            particle_ticks: 0.0,
+        }
+    }
+
+    /// Adds the free path a particle completed by colliding.
+    pub fn add_free_path(&mut self, free_path: f64) {
+        self.collisions += 1.0;
+        self.free_path_total += free_path;
+
+        if self.bin_width > 0.0 {
+            let bin = (free_path / self.bin_width) as usize;
+            if bin < self.free_path_counts.len() {
+                self.free_path_counts[bin] += 1.0;
+            }
+        }
+    }
+
+    pub fn end_tick(&mut self, particle_count: usize) {
+        self.particle_ticks += particle_count as f64;
+    }
+
+    pub fn mean_free_path(&self) -> f64 {
+        if self.collisions == 0.0 {
+            return 0.0;
+        }
+        self.free_path_total / self.collisions
+    }
+
+    /// Collisions per particle per tick.
+    pub fn collision_rate(&self) -> f64 {
+        if self.particle_ticks == 0.0 {
+            return 0.0;
+        }
+        self.collisions / self.particle_ticks
+    }
+
+    /// Probability density of the completed free paths, in the bins given
+    /// at construction. Longer paths count towards the total but fall outside.
+    pub fn free_path_histogram(&self) -> Vec<f64> {
+        if self.collisions == 0.0 || self.bin_width <= 0.0 {
+            return vec![0.0; self.free_path_counts.len()];
+        }
+
+        let scale = 1.0 / (self.collisions * self.bin_width);
+        self.free_path_counts
+            .iter()
+            .map(|count| count * scale)
+            .collect()
+    }
+}
+
+/// Fraction of particles with each collision count from zero to `max_count`;
+/// busier particles fall in the last bin, which is at most [`MAX_BINS`].
+pub fn collision_count_histogram(particles: &[Particle], max_count: usize) -> Vec<f64> {
+    let max_count = max_count.min(MAX_BINS - 1);
+    let mut histogram = vec![0.0; max_count + 1];
+    if particles.is_empty() {
+        return histogram;
+    }
+
+    for particle in particles.iter() {
+        histogram[(particle.collision_count as usize).min(max_count)] += 1.0;
+    }
+    for value in histogram.iter_mut() {
+        *value /= particles.len() as f64;
+    }
+    histogram
+}
+
+/// Unwrapped positions and velocities of the particles at one time origin.
+#[derive(Clone, Debug)]
+struct TimeOrigin {
+    age: usize,
+    particles: Vec<(usize, Vec2, Vec2)>,
+}
+
+/// Mean squared displacement and velocity autocorrelation for lags of up to
+/// `window` ticks, averaged over particles and over time origins taken
+/// every `interval` ticks.
+#[derive(Clone, Debug)]
+pub struct Transport {
+    interval: usize,
+    ticks: usize,
+    origins: Vec<TimeOrigin>,
+    squared_displacements: Vec<f64>,
+    correlations: Vec<f64>,
+    counts: Vec<f64>,
+}
+
+impl Transport {
+    pub fn new(window: usize, interval: usize) -> Transport {
+        let window = window.min(MAX_BINS);
+        Transport {
+            interval: interval.max(1),
+            ticks: 0,
+            origins: Vec::new(),
+            squared_displacements: vec![0.0; window],
+            correlations: vec![0.0; window],
+            counts: vec![0.0; window],
+        }
+    }
+
+    /// Follows the particles after those at the sorted `removed` indices were
+    /// deleted, dropping the removed ones from every origin.
+    pub fn remap(&mut self, removed: &[usize]) {
+        if removed.is_empty() {
+            return;
+        }
+
+        for origin in self.origins.iter_mut() {
+            origin
+                .particles
+                .retain_mut(|(index, _, _)| match remapped_index(*index, removed) {
+                    Some(remapped) => {
+                        *index = remapped;
+                        true
+                    }
+                    None => false,
+                });
+        }
+    }
+
+    /// Adds the current positions and velocities to the averages, starting a
+    /// new origin every `interval` ticks.
+    pub fn sample(&mut self, particles: &[Particle]) {
+        let window = self.counts.len();
+        if window == 0 {
+            return;
+        }
+
+        // `is_multiple_of` needs Rust 1.87.
+        #[allow(clippy::manual_is_multiple_of)]
+        let is_origin = self.ticks % self.interval == 0;
+        if is_origin {
+            self.origins.push(TimeOrigin {
+                age: 0,
+                particles: particles
+                    .iter()
+                    .enumerate()
+                    .map(|(i, particle)| {
+                        (i, unwrapped_position(particle), (particle.dx, particle.dy))
+                    })
+                    .collect(),
+            });
+        }
+        self.ticks += 1;
+
+        for origin in self.origins.iter_mut() {
+            let lag = origin.age;
+            for &(index, position, velocity) in origin.particles.iter() {
+                let particle = &particles[index];
+                let (x, y) = unwrapped_position(particle);
+                self.squared_displacements[lag] +=
+                    (x - position.0).powi(2) + (y - position.1).powi(2);
+                self.correlations[lag] += particle.dx * velocity.0 + particle.dy * velocity.1;
+                self.counts[lag] += 1.0;
+            }
+            origin.age += 1;
+        }
+        self.origins.retain(|origin| origin.age < window);
+    }
+
+    /// `<|r(t) - r(0)|²>` for each lag `t` in ticks.
+    pub fn mean_squared_displacement(&self) -> Vec<f64> {
+        average(&self.squared_displacements, &self.counts)
+    }
+
+    /// `<v(t) · v(0)>` for each lag `t` in ticks.
+    pub fn velocity_autocorrelation(&self) -> Vec<f64> {
+        average(&self.correlations, &self.counts)
+    }
+
+    /// Einstein relation `MSD = 4 D t`, fitted by least squares over the
+    /// second half of the window, where motion has become diffusive.
+    pub fn diffusion_from_displacement(&self) -> f64 {
+        let msd = self.mean_squared_displacement();
+        let start = msd.len() / 2;
+        let points: Vec<(f64, f64)> = msd
+            .iter()
+            .enumerate()
+            .skip(start)
+            .filter(|(lag, _)| self.counts[*lag] > 0.0)
+            .map(|(lag, value)| (lag as f64, *value))
+            .collect();
+        if points.len() < 2 {
+            return 0.0;
+        }
+
+        let n = points.len() as f64;
+        let mean_t = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_msd = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let covariance: f64 = points
+            .iter()
+            .map(|p| (p.0 - mean_t) * (p.1 - mean_msd))
+            .sum();
+        let variance: f64 = points.iter().map(|p| (p.0 - mean_t).powi(2)).sum();
+        covariance / variance / 4.0
+    }
+
+    /// Green-Kubo relation `D = ½ ∫ <v(t) · v(0)> dt`, integrated with the
+    /// trapezoidal rule over the window.
+    pub fn diffusion_from_autocorrelation(&self) -> f64 {
+        let vacf = self.velocity_autocorrelation();
+        match (vacf.first(), vacf.last()) {
+            (Some(first), Some(last)) => 0.5 * (vacf.iter().sum::<f64>() - 0.5 * (first + last)),
+            _ => 0.0,
+        }
+    }
+}
+
+fn unwrapped_position(particle: &Particle) -> Vec2 {
+    (
+        particle.x + particle.unwrap_x,
+        particle.y + particle.unwrap_y,
+    )
+}
+
+fn average(sums: &[f64], counts: &[f64]) -> Vec<f64> {
+    sums.iter()
+        .zip(counts.iter())
+        .map(|(sum, count)| if *count > 0.0 { sum / count } else { 0.0 })
+        .collect()
+}
+
+/// Bessel function of the first kind of order zero, from the polynomial
+/// approximations in Abramowitz and Stegun 9.4.1 and 9.4.3.
+fn bessel_j0(x: f64) -> f64 {
+    let x = x.abs();
+    if x <= 3.0 {
+        let y = (x / 3.0).powi(2);
+        return 1.0
+            + y * (-2.2499997
+                + y * (1.2656208
+                    + y * (-0.3163866 + y * (0.0444479 + y * (-0.0039444 + y * 0.00021)))));
+    }
+
+    let z = 3.0 / x;
+    let f0 = 0.79788456
+        + z * (-0.00000077
+            + z * (-0.0055274
+                + z * (-0.00009512 + z * (0.00137237 + z * (-0.00072805 + z * 0.00014476)))));
+    let theta0 = x - std::f64::consts::FRAC_PI_4
+        + z * (-0.04166397
+            + z * (-0.00003954
+                + z * (0.00262573 + z * (-0.00054125 + z * (-0.00029333 + z * 0.00013558)))));
+    f0 * theta0.cos() / x.sqrt()
+}
@@ -0,0 +1,114 @@
+use crate::{
+    geometry::{closest_point_on_segment, dot, length, normalize, scale, sub, Vec2},
+    particle::Particle,
+    polygon::{polygon_segment_contact, Contact, Polygon},
+};
+
+/// Immovable capsule: the set of points within `radius` of the segment from
+/// `(x1, y1)` to `(x2, y2)`. A plain line segment has a radius of zero.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Obstacle {
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+    pub radius: f64,
+}
+
+impl Obstacle {
+    pub fn segment(x1: f64, y1: f64, x2: f64, y2: f64) -> Obstacle {
+        Obstacle::capsule(x1, y1, x2, y2, 0.0)
+    }
+
+    pub fn capsule(x1: f64, y1: f64, x2: f64, y2: f64, radius: f64) -> Obstacle {
+        Obstacle {
+            x1,
+            y1,
+            x2,
+            y2,
+            radius: radius.max(0.0),
+        }
+    }
+
+    /// One capsule per consecutive pair of `points`.
+    pub fn polyline(points: &[Vec2], radius: f64) -> Vec<Obstacle> {
+        points
+            .windows(2)
+            .map(|pair| Obstacle::capsule(pair[0].0, pair[0].1, pair[1].0, pair[1].1, radius))
+            .collect()
+    }
+
+    fn start(&self) -> Vec2 {
+        (self.x1, self.y1)
+    }
+
+    fn end(&self) -> Vec2 {
+        (self.x2, self.y2)
+    }
+
+    /// Contact with a disc at `center`, normal pointing from the obstacle to the disc.
+    pub fn circle_contact(&self, center: Vec2, radius: f64) -> Option<Contact> {
+        let closest = closest_point_on_segment(center, self.start(), self.end());
+        let offset = sub(center, closest);
+        let distance = length(offset);
+        let reach = self.radius + radius;
+
+        if distance >= reach {
+            return None;
+        }
+
+        let normal = if distance > 0.0 {
+            scale(offset, 1.0 / distance)
+        } else {
+            let along = sub(self.end(), self.start());
+            normalize((-along.1, along.0))
+        };
+
+        Some(Contact {
+            normal,
+            depth: reach - distance,
+            point: closest,
+        })
+    }
+}
+
+/// Pushes particles out of the obstacles and reflects the normal component of
+/// their velocity, scaled by `restitution`.
+pub fn collide_particles_with_obstacles(
+    particles: &mut [Particle],
+    obstacles: &[Obstacle],
+    restitution: f64,
+) {
+    for particle in particles.iter_mut() {
+        for obstacle in obstacles.iter() {
+            let contact = match obstacle.circle_contact((particle.x, particle.y), particle.radius) {
+                Some(contact) => contact,
+                None => continue,
+            };
+
+            particle.x += contact.normal.0 * contact.depth;
+            particle.y += contact.normal.1 * contact.depth;
+
+            let velocity_along_normal = dot((particle.dx, particle.dy), contact.normal);
+            if velocity_along_normal < 0.0 {
+                particle.dx -= (1.0 + restitution) * velocity_along_normal * contact.normal.0;
+                particle.dy -= (1.0 + restitution) * velocity_along_normal * contact.normal.1;
+            }
+        }
+    }
+}
+
+pub fn collide_polygon_with_obstacles(
+    polygon: &mut Polygon,
+    obstacles: &[Obstacle],
+    restitution: f64,
+) {
+    for obstacle in obstacles.iter() {
+        if let Some(contact) =
+            polygon_segment_contact(polygon, obstacle.start(), obstacle.end(), obstacle.radius)
+        {
+            polygon.resolve_static_contact(&contact, restitution);
+        }
+    }
+}
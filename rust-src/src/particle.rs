@@ -1,6 +1,9 @@
 use wasm_bindgen::prelude::*;
 
+/// Laid out as consecutive `f64`s so JavaScript can read the particle buffer
+/// through a `Float64Array` with a stride of [`particle_stride`].
 #[wasm_bindgen]
+#[repr(C)]
 #[derive(Clone, Copy)]
 pub struct Particle {
     pub x: f64,
@@ -11,4 +14,110 @@ pub struct Particle {
     pub color_r: f64,
     pub color_g: f64,
     pub color_b: f64,
+    /// Ticks left before the particle is removed; infinite unless it was
+    /// spawned by an emitter with a finite lifetime.
+    pub lifetime: f64,
+    /// Defaults to the radius, which is what collisions used as mass before
+    /// particles carried one of their own.
+    pub mass: f64,
+    /// Electric charge; neutral by default.
+    pub charge: f64,
+    /// Total distance periodic edges have moved the particle back by, so
+    /// `(x + unwrap_x, y + unwrap_y)` is where it would be without wrapping.
+    pub unwrap_x: f64,
+    pub unwrap_y: f64,
+    /// Collisions with other particles since it was spawned or the collision
+    /// statistics were last started.
+    pub collision_count: f64,
+    /// Distance travelled since its last collision with another particle or
+    /// since the collision statistics were last started.
+    pub free_path: f64,
+    /// Unique within its universe and never reused. Particles are only ever
+    /// appended with fresh ids or removed, so they stay sorted by id.
+    pub id: f64,
+    /// Collision group from 0 to 31.
+    pub group: f64,
+    /// Bits of the groups this particle collides with; all of them by
+    /// default. Walls and obstacles ignore it.
+    pub collision_mask: f64,
+}
+
+impl Particle {
+    pub fn new(x: f64, y: f64, dx: f64, dy: f64, radius: f64) -> Particle {
+        Particle {
+            x,
+            y,
+            dx,
+            dy,
+            radius,
+            color_r: 255.0,
+            color_g: 255.0,
+            color_b: 255.0,
+            lifetime: f64::INFINITY,
+            mass: radius,
+            charge: 0.0,
+            unwrap_x: 0.0,
+            unwrap_y: 0.0,
+            collision_count: 0.0,
+            free_path: 0.0,
+            id: 0.0,
+            group: 0.0,
+            collision_mask: u32::MAX as f64,
+        }
+    }
+
+    /// True when both particles' masks include the other's group, so the
+    /// pair collides.
+    pub fn collides_with(&self, other: &Particle) -> bool {
+        let group_bit = |particle: &Particle| 1_u32.checked_shl(particle.group as u32).unwrap_or(0);
+        self.collision_mask as u32 & group_bit(other) != 0
+            && other.collision_mask as u32 & group_bit(self) != 0
+    }
+
+    /// Counts a collision with another particle and returns the free path
+    /// that it ended.
+    pub fn record_collision(&mut self) -> f64 {
+        let free_path = self.free_path;
+        self.collision_count += 1.0;
+        self.free_path = 0.0;
+        free_path
+    }
+}
+
+/// Number of `f64` slots each particle occupies in the shared buffer.
+#[wasm_bindgen]
+pub fn particle_stride() -> usize {
+    std::mem::size_of::<Particle>() / std::mem::size_of::<f64>()
+}
+
+/// Index of the particle with `id`, found by binary search since particles
+/// are kept sorted by id.
+pub fn find_particle(particles: &[Particle], id: f64) -> Option<usize> {
+    particles
+        .binary_search_by(|particle| particle.id.total_cmp(&id))
+        .ok()
+}
+
+/// Removes the particles at `indices`, which must be sorted in ascending order,
+/// keeping the remaining particles in their original order.
+pub fn remove_particles(particles: &mut Vec<Particle>, indices: &[usize]) {
+    if indices.is_empty() {
+        return;
+    }
+
+    let mut index = 0;
+    particles.retain(|_| {
+        let keep = indices.binary_search(&index).is_err();
+        index += 1;
+        keep
+    });
+}
+
+/// Index that the particle at `index` moves to once the particles at the
+/// sorted `removed` indices are deleted, or `None` if it is one of them.
+pub fn remapped_index(index: usize, removed: &[usize]) -> Option<usize> {
+    match removed.binary_search(&index) {
+        Ok(_) => None,
+        Err(shift) => Some(index - shift),
+    }
 }
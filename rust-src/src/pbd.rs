@@ -0,0 +1,324 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    boundary::Container,
+    collision::{candidate_pairs, CONTACT_SLOP},
+    constraint::{Constraint, ConstraintKind},
+    events::{CollisionEvent, WALL_ID},
+    geometry::{dot, Vec2},
+    obstacle::Obstacle,
+    particle::Particle,
+    rectangle::Rectangle,
+};
+
+/// How particle contacts, walls and constraints are resolved each tick.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Solver {
+    /// Single pass of velocity impulses per contact.
+    Impulse = 0,
+    /// Iterated position projection (XPBD), stable for dense piles and stacks.
+    PositionBased = 1,
+}
+
+/// Approach speed below which contacts do not bounce, so resting piles settle.
+const REST_SPEED: f64 = 0.1;
+
+/// Advances the particles by one tick with extended position-based dynamics:
+/// predict positions from the current velocities, project contacts, walls,
+/// obstacles and constraints `iterations` times, then derive the new
+/// velocities from the corrected positions and apply restitution. Contacts
+/// that bounce are logged to `events` when given. Returns the momentum the
+/// container walls took up.
+#[allow(clippy::too_many_arguments)]
+pub fn step_position_based(
+    particles: &mut [Particle],
+    constraints: &[Constraint],
+    obstacles: &[Obstacle],
+    container: &Container,
+    bounds: &Rectangle,
+    iterations: u32,
+    restitution: f64,
+    wall_restitution: f64,
+    mut events: Option<&mut Vec<CollisionEvent>>,
+) -> f64 {
+    let previous: Vec<(Vec2, Vec2)> = particles
+        .iter()
+        .map(|particle| ((particle.x, particle.y), (particle.dx, particle.dy)))
+        .collect();
+
+    for particle in particles.iter_mut() {
+        particle.x += particle.dx;
+        particle.y += particle.dy;
+    }
+
+    let pairs = candidate_pairs(particles, container, bounds);
+    let mut multipliers = vec![0.0; constraints.len()];
+
+    for _ in 0..iterations.max(1) {
+        for &(i, j) in pairs.iter() {
+            project_contact(particles, i, j, container, bounds);
+        }
+
+        for (constraint, multiplier) in constraints.iter().zip(multipliers.iter_mut()) {
+            project_constraint(particles, constraint, multiplier, container, bounds);
+        }
+
+        for particle in particles.iter_mut() {
+            for obstacle in obstacles.iter() {
+                if let Some(contact) =
+                    obstacle.circle_contact((particle.x, particle.y), particle.radius)
+                {
+                    particle.x += contact.normal.0 * contact.depth;
+                    particle.y += contact.normal.1 * contact.depth;
+                }
+            }
+            container.project(particle, bounds, 0.0);
+        }
+    }
+
+    for (particle, (position, _)) in particles.iter_mut().zip(previous.iter()) {
+        particle.dx = particle.x - position.0;
+        particle.dy = particle.y - position.1;
+    }
+
+    let mut bounces = Vec::new();
+    for &(i, j) in pairs.iter() {
+        if let Some(normal) =
+            apply_contact_restitution(particles, &previous, i, j, container, bounds, restitution)
+        {
+            bounces.push((i, j, normal));
+        }
+    }
+
+    if let Some(events) = events.as_mut() {
+        for (i, j, normal) in bounces {
+            let (a, b) = (&particles[i], &particles[j]);
+            let velocities = (previous[i].1, previous[j].1);
+            events.push(CollisionEvent::between(a, b, velocities, normal));
+        }
+    }
+
+    for constraint in constraints.iter() {
+        damp_constraint(particles, constraint, container, bounds);
+    }
+
+    let mut wall_impulse = 0.0;
+    for (particle, (_, velocity)) in particles.iter_mut().zip(previous.iter()) {
+        let mut normals: Vec<Vec2> = obstacles
+            .iter()
+            .filter_map(|obstacle| {
+                obstacle
+                    .circle_contact((particle.x, particle.y), particle.radius + CONTACT_SLOP)
+                    .map(|contact| contact.normal)
+            })
+            .collect();
+        let wall_normal = container.project(particle, bounds, CONTACT_SLOP);
+        normals.extend(wall_normal);
+
+        for normal in normals {
+            let approach = dot(*velocity, normal);
+            let current = dot((particle.dx, particle.dy), normal);
+            let target = if approach < -REST_SPEED {
+                -wall_restitution * approach
+            } else {
+                0.0
+            };
+            if current < target {
+                particle.dx += (target - current) * normal.0;
+                particle.dy += (target - current) * normal.1;
+            }
+        }
+
+        if let Some(normal) = wall_normal {
+            let change = dot((particle.dx, particle.dy), normal) - dot(*velocity, normal);
+            wall_impulse += particle.mass * change.max(0.0);
+
+            let approach = -dot(*velocity, normal);
+            if let Some(events) = events.as_mut() {
+                if approach > REST_SPEED && change > 0.0 {
+                    events.push(CollisionEvent::new(
+                        particle,
+                        WALL_ID,
+                        normal,
+                        approach,
+                        particle.mass * change,
+                    ));
+                }
+            }
+        }
+    }
+
+    wall_impulse
+}
+
+fn pair_mut(particles: &mut [Particle], i: usize, j: usize) -> (&mut Particle, &mut Particle) {
+    let (head, tail) = particles.split_at_mut(j);
+    (&mut head[i], &mut tail[0])
+}
+
+fn project_contact(
+    particles: &mut [Particle],
+    i: usize,
+    j: usize,
+    container: &Container,
+    bounds: &Rectangle,
+) {
+    let (a, b) = pair_mut(particles, i, j);
+    let (dx, dy) = container.displacement((a.x, a.y), (b.x, b.y), bounds);
+    let distance = (dx * dx + dy * dy).sqrt();
+    let overlap = a.radius + b.radius - distance;
+    if overlap <= 0.0 || distance == 0.0 {
+        return;
+    }
+
+    let (nx, ny) = (dx / distance, dy / distance);
+    let wa = 1.0 / a.mass;
+    let wb = 1.0 / b.mass;
+    let correction = overlap / (wa + wb);
+    a.x += nx * correction * wa;
+    a.y += ny * correction * wa;
+    b.x -= nx * correction * wb;
+    b.y -= ny * correction * wb;
+}
+
+/// Returns the contact normal if the pair came together fast enough to bounce.
+fn apply_contact_restitution(
+    particles: &mut [Particle],
+    previous: &[(Vec2, Vec2)],
+    i: usize,
+    j: usize,
+    container: &Container,
+    bounds: &Rectangle,
+    restitution: f64,
+) -> Option<Vec2> {
+    let (a, b) = pair_mut(particles, i, j);
+    let (dx, dy) = container.displacement((a.x, a.y), (b.x, b.y), bounds);
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance == 0.0 || distance > a.radius + b.radius + CONTACT_SLOP {
+        return None;
+    }
+
+    let normal = (dx / distance, dy / distance);
+    let approach = dot(
+        (
+            previous[i].1 .0 - previous[j].1 .0,
+            previous[i].1 .1 - previous[j].1 .1,
+        ),
+        normal,
+    );
+    let current = dot((a.dx - b.dx, a.dy - b.dy), normal);
+    let target = if approach < -REST_SPEED {
+        -restitution * approach
+    } else {
+        0.0
+    };
+    if current < target {
+        let wa = 1.0 / a.mass;
+        let wb = 1.0 / b.mass;
+        let impulse = (target - current) / (wa + wb);
+        a.dx += normal.0 * impulse * wa;
+        a.dy += normal.1 * impulse * wa;
+        b.dx -= normal.0 * impulse * wb;
+        b.dy -= normal.1 * impulse * wb;
+    }
+
+    if approach < -REST_SPEED {
+        Some(normal)
+    } else {
+        None
+    }
+}
+
+/// Ordered pair of mutable particles for a constraint, or `None` if the
+/// constraint is degenerate.
+fn constraint_ends<'a>(
+    particles: &'a mut [Particle],
+    constraint: &Constraint,
+) -> Option<(&'a mut Particle, &'a mut Particle)> {
+    let (a, b) = (constraint.a, constraint.b);
+    if a == b || a >= particles.len() || b >= particles.len() {
+        return None;
+    }
+
+    if a < b {
+        Some(pair_mut(particles, a, b))
+    } else {
+        let (second, first) = pair_mut(particles, b, a);
+        Some((first, second))
+    }
+}
+
+/// XPBD distance constraint. Springs use a compliance of `1 / stiffness`;
+/// rigid links and taut ropes are infinitely stiff.
+fn project_constraint(
+    particles: &mut [Particle],
+    constraint: &Constraint,
+    multiplier: &mut f64,
+    container: &Container,
+    bounds: &Rectangle,
+) {
+    let (first, second) = match constraint_ends(particles, constraint) {
+        Some(ends) => ends,
+        None => return,
+    };
+
+    let (dx, dy) = container.displacement((second.x, second.y), (first.x, first.y), bounds);
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance == 0.0 {
+        return;
+    }
+
+    let error = distance - constraint.length;
+    let compliance = match constraint.kind {
+        ConstraintKind::Rigid => 0.0,
+        ConstraintKind::Rope if error <= 0.0 => return,
+        ConstraintKind::Rope => 0.0,
+        ConstraintKind::Spring { stiffness, .. } if stiffness > 0.0 => 1.0 / stiffness,
+        ConstraintKind::Spring { .. } => return,
+    };
+
+    let (nx, ny) = (dx / distance, dy / distance);
+    let wa = 1.0 / first.mass;
+    let wb = 1.0 / second.mass;
+    let delta = (error - compliance * *multiplier) / (wa + wb + compliance);
+    *multiplier += delta;
+
+    first.x += nx * delta * wa;
+    first.y += ny * delta * wa;
+    second.x -= nx * delta * wb;
+    second.y -= ny * delta * wb;
+}
+
+fn damp_constraint(
+    particles: &mut [Particle],
+    constraint: &Constraint,
+    container: &Container,
+    bounds: &Rectangle,
+) {
+    let damping = match constraint.kind {
+        ConstraintKind::Spring { damping, .. } => damping,
+        _ => return,
+    };
+    let (first, second) = match constraint_ends(particles, constraint) {
+        Some(ends) => ends,
+        None => return,
+    };
+
+    let (dx, dy) = container.displacement((second.x, second.y), (first.x, first.y), bounds);
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance == 0.0 {
+        return;
+    }
+
+    let (nx, ny) = (dx / distance, dy / distance);
+    let wa = 1.0 / first.mass;
+    let wb = 1.0 / second.mass;
+    let relative_speed = (second.dx - first.dx) * nx + (second.dy - first.dy) * ny;
+    let impulse =
+        (damping * relative_speed).clamp(-relative_speed.abs(), relative_speed.abs()) / (wa + wb);
+    first.dx += nx * impulse * wa;
+    first.dy += ny * impulse * wa;
+    second.dx -= nx * impulse * wb;
+    second.dy -= ny * impulse * wb;
+}
@@ -0,0 +1,103 @@
+use crate::{particle::Particle, quad_tree::QuadTree, rectangle::Rectangle};
+
+/// Area to look for particles in. A point picks the particles whose disc
+/// covers it, rectangles and circles the particles whose centers they hold.
+#[derive(Clone, Copy, Debug)]
+pub enum Region {
+    Point { x: f64, y: f64 },
+    Rectangle(Rectangle),
+    Circle { x: f64, y: f64, radius: f64 },
+}
+
+impl Region {
+    pub fn holds(&self, particle: &Particle) -> bool {
+        match *self {
+            Region::Point { x, y } => {
+                let dx = particle.x - x;
+                let dy = particle.y - y;
+                dx * dx + dy * dy <= particle.radius * particle.radius
+            }
+            Region::Rectangle(rectangle) => rectangle.contains_point(particle.x, particle.y),
+            Region::Circle { x, y, radius } => {
+                let dx = particle.x - x;
+                let dy = particle.y - y;
+                dx * dx + dy * dy <= radius * radius
+            }
+        }
+    }
+
+    /// Box around every center that can be held, given the largest radius.
+    fn bounding_box(&self, max_radius: f64) -> Rectangle {
+        match *self {
+            Region::Point { x, y } => Rectangle::new(x, y, max_radius, max_radius),
+            Region::Rectangle(rectangle) => rectangle,
+            Region::Circle { x, y, radius } => Rectangle::new(x, y, radius, radius),
+        }
+    }
+}
+
+/// Quadtree over a snapshot of the particles for answering picking queries
+/// without scanning them all. Results are particle ids, as the snapshot does
+/// not follow the particle buffer.
+#[derive(Clone)]
+pub struct SpatialIndex {
+    tree: QuadTree,
+    max_radius: f64,
+}
+
+impl SpatialIndex {
+    pub fn new(particles: &[Particle]) -> SpatialIndex {
+        let max_radius = particles
+            .iter()
+            .fold(0.0_f64, |radius, particle| radius.max(particle.radius));
+
+        let mut min = (0.0_f64, 0.0_f64);
+        let mut max = (0.0_f64, 0.0_f64);
+        if let Some(first) = particles.first() {
+            min = (first.x, first.y);
+            max = (first.x, first.y);
+        }
+        for particle in particles.iter() {
+            min = (min.0.min(particle.x), min.1.min(particle.y));
+            max = (max.0.max(particle.x), max.1.max(particle.y));
+        }
+
+        // The tree only takes particles lying strictly inside it.
+        let boundary = Rectangle::from_corners(min.0, min.1, max.0, max.1).expand(max_radius + 1.0);
+        let mut tree = QuadTree::new(boundary, 4);
+        for particle in particles.iter() {
+            let _ = tree.insert(particle);
+        }
+
+        SpatialIndex { tree, max_radius }
+    }
+
+    /// Ids of the particles in `region`, in ascending order.
+    pub fn query(&self, region: &Region) -> Vec<u32> {
+        // The tree returns whole discs inside the range, so pad it to reach
+        // every center that could count.
+        let range = region
+            .bounding_box(self.max_radius)
+            .expand(self.max_radius + 1.0);
+
+        let mut found = Vec::new();
+        self.tree.query(&range, &mut found);
+
+        let mut ids: Vec<u32> = found
+            .iter()
+            .filter(|particle| region.holds(particle))
+            .map(|particle| particle.id as u32)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Id of the particle whose surface is closest to `(x, y)`, if it lies
+    /// within `max_distance`.
+    pub fn nearest(&self, x: f64, y: f64, max_distance: f64) -> Option<u32> {
+        let mut best = None;
+        self.tree
+            .nearest(x, y, max_distance, self.max_radius, &mut best);
+        best.map(|(_, particle)| particle.id as u32)
+    }
+}
@@ -0,0 +1,76 @@
+use crate::particle::{remapped_index, Particle};
+
+/// State of the user's pointer: an optional radial force around it and an
+/// optional particle held on a damped spring.
+#[derive(Clone, Copy, Debug)]
+pub struct Pointer {
+    pub x: f64,
+    pub y: f64,
+    /// Positive strengths attract towards the pointer, negative ones repel.
+    pub strength: f64,
+    /// Reach of the radial force; the force fades linearly to zero at this distance.
+    pub radius: f64,
+    pub is_force_active: bool,
+    pub grabbed: Option<usize>,
+    pub stiffness: f64,
+    pub damping: f64,
+}
+
+impl Pointer {
+    pub fn new() -> Pointer {
+        Pointer {
+            x: 0.0,
+            y: 0.0,
+            strength: 0.0,
+            radius: 0.0,
+            is_force_active: false,
+            grabbed: None,
+            stiffness: 0.05,
+            damping: 0.2,
+        }
+    }
+
+    /// Follows the grabbed particle after those at the sorted `removed`
+    /// indices were deleted, releasing it if it was one of them.
+    pub fn remap(&mut self, removed: &[usize]) {
+        self.grabbed = self
+            .grabbed
+            .and_then(|index| remapped_index(index, removed));
+    }
+
+    /// Applies the radial force and the drag spring for one tick.
+    pub fn apply(&mut self, particles: &mut [Particle]) {
+        if self.is_force_active && self.radius > 0.0 {
+            for particle in particles.iter_mut() {
+                let dx = self.x - particle.x;
+                let dy = self.y - particle.y;
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance == 0.0 || distance >= self.radius {
+                    continue;
+                }
+
+                let magnitude = self.strength * (1.0 - distance / self.radius);
+                particle.dx += magnitude * dx / distance;
+                particle.dy += magnitude * dy / distance;
+            }
+        }
+
+        if let Some(index) = self.grabbed {
+            match particles.get_mut(index) {
+                Some(particle) => {
+                    particle.dx +=
+                        self.stiffness * (self.x - particle.x) - self.damping * particle.dx;
+                    particle.dy +=
+                        self.stiffness * (self.y - particle.y) - self.damping * particle.dy;
+                }
+                None => self.grabbed = None,
+            }
+        }
+    }
+}
+
+impl Default for Pointer {
+    fn default() -> Pointer {
+        Pointer::new()
+    }
+}
@@ -0,0 +1,470 @@
+use crate::{
+    boundary::Container,
+    geometry::{
+        add, closest_point_on_segment, convex_hull, cross, dot, length, normalize, polygon_area,
+        polygon_centroid, rotate, scale, sub, Vec2,
+    },
+    obstacle::{collide_polygon_with_obstacles, Obstacle},
+    particle::Particle,
+    rectangle::Rectangle,
+};
+
+/// Rigid convex body. `vertices` are stored relative to the centroid in
+/// counter-clockwise order and rotated by `angle` when placed in the world.
+#[derive(Clone)]
+pub struct Polygon {
+    pub x: f64,
+    pub y: f64,
+    pub dx: f64,
+    pub dy: f64,
+    pub angle: f64,
+    pub angular_velocity: f64,
+    pub mass: f64,
+    pub inertia: f64,
+    pub color_r: f64,
+    pub color_g: f64,
+    pub color_b: f64,
+    pub vertices: Vec<Vec2>,
+}
+
+/// Contact between two shapes; `normal` points from the first shape towards the second.
+#[derive(Clone, Copy, Debug)]
+pub struct Contact {
+    pub normal: Vec2,
+    pub depth: f64,
+    pub point: Vec2,
+}
+
+impl Polygon {
+    /// Builds a body from the convex hull of `points`, given in world space.
+    /// Returns `None` for degenerate input (fewer than three distinct corners).
+    pub fn new(points: &[Vec2], dx: f64, dy: f64) -> Option<Polygon> {
+        let hull = convex_hull(points);
+        let area = polygon_area(&hull);
+        if hull.len() < 3 || area <= 0.0 {
+            return None;
+        }
+
+        let centroid = polygon_centroid(&hull);
+        let vertices: Vec<Vec2> = hull.iter().map(|v| sub(*v, centroid)).collect();
+
+        // Particles default to their radius as mass, so a polygon weighs as
+        // much as the circle of equal area would.
+        let mass = (area / std::f64::consts::PI).sqrt();
+
+        let n = vertices.len();
+        let mut second_moment = 0.0;
+        for i in 0..n {
+            let a = vertices[i];
+            let b = vertices[(i + 1) % n];
+            second_moment += cross(a, b) * (dot(a, a) + dot(a, b) + dot(b, b));
+        }
+        let inertia = mass * (second_moment / 12.0) / area;
+
+        Some(Polygon {
+            x: centroid.0,
+            y: centroid.1,
+            dx,
+            dy,
+            angle: 0.0,
+            angular_velocity: 0.0,
+            mass,
+            inertia,
+            color_r: 255.0,
+            color_g: 255.0,
+            color_b: 255.0,
+            vertices,
+        })
+    }
+
+    pub fn new_box(x: f64, y: f64, width: f64, height: f64, dx: f64, dy: f64) -> Option<Polygon> {
+        let w = width / 2.0;
+        let h = height / 2.0;
+        Polygon::new(
+            &[
+                (x - w, y - h),
+                (x + w, y - h),
+                (x + w, y + h),
+                (x - w, y + h),
+            ],
+            dx,
+            dy,
+        )
+    }
+
+    pub fn position(&self) -> Vec2 {
+        (self.x, self.y)
+    }
+
+    pub fn world_vertices(&self) -> Vec<Vec2> {
+        self.vertices
+            .iter()
+            .map(|v| add(rotate(*v, self.angle), self.position()))
+            .collect()
+    }
+
+    pub fn bounding_box(&self) -> Rectangle {
+        let vertices = self.world_vertices();
+        let mut min = vertices[0];
+        let mut max = vertices[0];
+        for v in vertices.iter().skip(1) {
+            min = (min.0.min(v.0), min.1.min(v.1));
+            max = (max.0.max(v.0), max.1.max(v.1));
+        }
+        Rectangle::from_corners(min.0, min.1, max.0, max.1)
+    }
+
+    fn inverse_mass(&self) -> f64 {
+        1.0 / self.mass
+    }
+
+    fn inverse_inertia(&self) -> f64 {
+        1.0 / self.inertia
+    }
+
+    fn velocity_at(&self, point: Vec2) -> Vec2 {
+        let r = sub(point, self.position());
+        (
+            self.dx - self.angular_velocity * r.1,
+            self.dy + self.angular_velocity * r.0,
+        )
+    }
+
+    fn apply_impulse(&mut self, impulse: Vec2, r: Vec2) {
+        self.dx += impulse.0 * self.inverse_mass();
+        self.dy += impulse.1 * self.inverse_mass();
+        self.angular_velocity += cross(r, impulse) * self.inverse_inertia();
+    }
+
+    /// Bounces the body off an immovable surface. `normal` points out of the
+    /// surface, towards the body.
+    pub fn resolve_static_contact(&mut self, contact: &Contact, restitution: f64) {
+        let normal = contact.normal;
+        let r = sub(contact.point, self.position());
+        let velocity_along_normal = dot(self.velocity_at(contact.point), normal);
+
+        if velocity_along_normal < 0.0 {
+            let r_n = cross(r, normal);
+            let denominator = self.inverse_mass() + r_n * r_n * self.inverse_inertia();
+            let j = -(1.0 + restitution) * velocity_along_normal / denominator;
+            self.apply_impulse(scale(normal, j), r);
+        }
+
+        self.x += normal.0 * contact.depth;
+        self.y += normal.1 * contact.depth;
+    }
+
+    /// Bounces the body off the sides of `bounds` flagged in `solid`, given in
+    /// left, right, top, bottom order.
+    pub fn bounce_off_walls(&mut self, bounds: &Rectangle, solid: [bool; 4], restitution: f64) {
+        let vertices = self.world_vertices();
+        let walls = [
+            ((1.0, 0.0), bounds.min_x()),
+            ((-1.0, 0.0), -bounds.max_x()),
+            ((0.0, 1.0), bounds.min_y()),
+            ((0.0, -1.0), -bounds.max_y()),
+        ];
+
+        for ((normal, offset), _) in walls.iter().zip(solid.iter()).filter(|(_, solid)| **solid) {
+            let deepest = vertices
+                .iter()
+                .map(|v| (*v, offset - dot(*v, *normal)))
+                .fold(None, |best: Option<(Vec2, f64)>, candidate| match best {
+                    Some(b) if b.1 >= candidate.1 => Some(b),
+                    _ => Some(candidate),
+                });
+
+            if let Some((point, depth)) = deepest {
+                if depth > 0.0 {
+                    let contact = Contact {
+                        normal: *normal,
+                        depth,
+                        point,
+                    };
+                    self.resolve_static_contact(&contact, restitution);
+                }
+            }
+        }
+    }
+}
+
+fn edge_normal(vertices: &[Vec2], i: usize) -> Vec2 {
+    let a = vertices[i];
+    let b = vertices[(i + 1) % vertices.len()];
+    let edge = sub(b, a);
+    normalize((edge.1, -edge.0))
+}
+
+/// Largest separation of `b` from any face of `a`, with the face normal.
+fn max_separation(a: &[Vec2], b: &[Vec2]) -> (f64, Vec2) {
+    let mut best = (f64::NEG_INFINITY, (0.0, 0.0));
+    for i in 0..a.len() {
+        let normal = edge_normal(a, i);
+        let separation = b
+            .iter()
+            .map(|v| dot(sub(*v, a[i]), normal))
+            .fold(f64::INFINITY, f64::min);
+        if separation > best.0 {
+            best = (separation, normal);
+        }
+    }
+    best
+}
+
+fn support_point(vertices: &[Vec2], direction: Vec2) -> Vec2 {
+    *vertices
+        .iter()
+        .min_by(|p, q| {
+            dot(**p, direction)
+                .partial_cmp(&dot(**q, direction))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap()
+}
+
+/// Separating-axis test between two convex polygons.
+pub fn polygon_polygon_contact(a: &Polygon, b: &Polygon) -> Option<Contact> {
+    let va = a.world_vertices();
+    let vb = b.world_vertices();
+
+    let (separation_a, normal_a) = max_separation(&va, &vb);
+    if separation_a > 0.0 {
+        return None;
+    }
+    let (separation_b, normal_b) = max_separation(&vb, &va);
+    if separation_b > 0.0 {
+        return None;
+    }
+
+    if separation_a >= separation_b {
+        Some(Contact {
+            normal: normal_a,
+            depth: -separation_a,
+            point: support_point(&vb, normal_a),
+        })
+    } else {
+        Some(Contact {
+            normal: scale(normal_b, -1.0),
+            depth: -separation_b,
+            point: support_point(&va, normal_b),
+        })
+    }
+}
+
+/// Separating-axis test between a polygon and a capsule around the segment
+/// `start`-`end`, normal pointing from the capsule to the polygon.
+pub fn polygon_segment_contact(
+    polygon: &Polygon,
+    start: Vec2,
+    end: Vec2,
+    radius: f64,
+) -> Option<Contact> {
+    let vertices = polygon.world_vertices();
+    let segment = [start, end];
+
+    let (separation_polygon, normal_polygon) = max_separation(&vertices, &segment);
+    let separation_polygon = separation_polygon - radius;
+    if separation_polygon > 0.0 {
+        return None;
+    }
+    let (separation_segment, normal_segment) = max_separation(&segment, &vertices);
+    let separation_segment = separation_segment - radius;
+    if separation_segment > 0.0 {
+        return None;
+    }
+
+    if separation_segment >= separation_polygon {
+        Some(Contact {
+            normal: normal_segment,
+            depth: -separation_segment,
+            point: support_point(&vertices, normal_segment),
+        })
+    } else {
+        Some(Contact {
+            normal: scale(normal_polygon, -1.0),
+            depth: -separation_polygon,
+            point: support_point(&segment, normal_polygon),
+        })
+    }
+}
+
+/// Contact between a convex polygon and a circular particle, normal pointing
+/// from the polygon to the particle.
+pub fn polygon_circle_contact(polygon: &Polygon, particle: &Particle) -> Option<Contact> {
+    let vertices = polygon.world_vertices();
+    let center = (particle.x, particle.y);
+
+    let mut inside = true;
+    let mut best_face = (f64::NEG_INFINITY, (0.0, 0.0));
+    let mut closest = vertices[0];
+    let mut closest_distance = f64::INFINITY;
+
+    for i in 0..vertices.len() {
+        let normal = edge_normal(&vertices, i);
+        let separation = dot(sub(center, vertices[i]), normal);
+        if separation > 0.0 {
+            inside = false;
+        }
+        if separation > best_face.0 {
+            best_face = (separation, normal);
+        }
+
+        let candidate =
+            closest_point_on_segment(center, vertices[i], vertices[(i + 1) % vertices.len()]);
+        let distance = length(sub(center, candidate));
+        if distance < closest_distance {
+            closest = candidate;
+            closest_distance = distance;
+        }
+    }
+
+    if inside {
+        let (separation, normal) = best_face;
+        return Some(Contact {
+            normal,
+            depth: particle.radius - separation,
+            point: sub(center, scale(normal, separation)),
+        });
+    }
+
+    if closest_distance >= particle.radius || closest_distance == 0.0 {
+        return None;
+    }
+
+    Some(Contact {
+        normal: scale(sub(center, closest), 1.0 / closest_distance),
+        depth: particle.radius - closest_distance,
+        point: closest,
+    })
+}
+
+pub fn resolve_polygon_polygon(
+    a: &mut Polygon,
+    b: &mut Polygon,
+    contact: &Contact,
+    restitution: f64,
+) {
+    let normal = contact.normal;
+    let ra = sub(contact.point, a.position());
+    let rb = sub(contact.point, b.position());
+    let relative_velocity = sub(b.velocity_at(contact.point), a.velocity_at(contact.point));
+    let velocity_along_normal = dot(relative_velocity, normal);
+
+    if velocity_along_normal < 0.0 {
+        let ra_n = cross(ra, normal);
+        let rb_n = cross(rb, normal);
+        let denominator = a.inverse_mass()
+            + b.inverse_mass()
+            + ra_n * ra_n * a.inverse_inertia()
+            + rb_n * rb_n * b.inverse_inertia();
+        let j = -(1.0 + restitution) * velocity_along_normal / denominator;
+        let impulse = scale(normal, j);
+        a.apply_impulse(scale(impulse, -1.0), ra);
+        b.apply_impulse(impulse, rb);
+    }
+
+    let total_inverse_mass = a.inverse_mass() + b.inverse_mass();
+    let correction = contact.depth / total_inverse_mass;
+    a.x -= normal.0 * correction * a.inverse_mass();
+    a.y -= normal.1 * correction * a.inverse_mass();
+    b.x += normal.0 * correction * b.inverse_mass();
+    b.y += normal.1 * correction * b.inverse_mass();
+}
+
+pub fn resolve_polygon_circle(
+    polygon: &mut Polygon,
+    particle: &mut Particle,
+    contact: &Contact,
+    restitution: f64,
+) {
+    let normal = contact.normal;
+    let r = sub(contact.point, polygon.position());
+    let particle_inverse_mass = 1.0 / particle.mass;
+    let relative_velocity = sub(
+        (particle.dx, particle.dy),
+        polygon.velocity_at(contact.point),
+    );
+    let velocity_along_normal = dot(relative_velocity, normal);
+
+    if velocity_along_normal < 0.0 {
+        let r_n = cross(r, normal);
+        let denominator =
+            polygon.inverse_mass() + particle_inverse_mass + r_n * r_n * polygon.inverse_inertia();
+        let j = -(1.0 + restitution) * velocity_along_normal / denominator;
+        let impulse = scale(normal, j);
+        polygon.apply_impulse(scale(impulse, -1.0), r);
+        particle.dx += impulse.0 * particle_inverse_mass;
+        particle.dy += impulse.1 * particle_inverse_mass;
+    }
+
+    let total_inverse_mass = polygon.inverse_mass() + particle_inverse_mass;
+    let correction = contact.depth / total_inverse_mass;
+    polygon.x -= normal.0 * correction * polygon.inverse_mass();
+    polygon.y -= normal.1 * correction * polygon.inverse_mass();
+    particle.x += normal.0 * correction * particle_inverse_mass;
+    particle.y += normal.1 * correction * particle_inverse_mass;
+}
+
+/// Advances every polygon by one step and resolves its collisions with the
+/// other polygons, the particles, the static obstacles and the container.
+pub fn tick_polygons(
+    polygons: &mut [Polygon],
+    particles: &mut [Particle],
+    obstacles: &[Obstacle],
+    container: &Container,
+    bounds: &Rectangle,
+    restitution: f64,
+    wall_restitution: f64,
+) {
+    for polygon in polygons.iter_mut() {
+        polygon.x += polygon.dx;
+        polygon.y += polygon.dy;
+        polygon.angle += polygon.angular_velocity;
+    }
+
+    for i in 0..polygons.len() {
+        let (first, rest) = polygons.split_at_mut(i + 1);
+        let polygon = first.last_mut().unwrap();
+
+        for other_polygon in rest.iter_mut() {
+            if !polygon
+                .bounding_box()
+                .intersects(&other_polygon.bounding_box())
+            {
+                continue;
+            }
+            if let Some(contact) = polygon_polygon_contact(polygon, other_polygon) {
+                resolve_polygon_polygon(polygon, other_polygon, &contact, restitution);
+            }
+        }
+
+        let polygon_box = polygon.bounding_box();
+        for particle in particles.iter_mut() {
+            if !polygon_box.intersects_circle(particle.x, particle.y, particle.radius) {
+                continue;
+            }
+            if let Some(contact) = polygon_circle_contact(polygon, particle) {
+                resolve_polygon_circle(polygon, particle, &contact, restitution);
+            }
+        }
+
+        collide_polygon_with_obstacles(polygon, obstacles, wall_restitution);
+        container.collide_polygon(polygon, bounds, wall_restitution);
+    }
+}
+
+/// Serializes polygons for rendering as consecutive records of
+/// `[vertex_count, color_r, color_g, color_b, x0, y0, x1, y1, ...]`.
+pub fn write_vertex_buffer(polygons: &[Polygon], buffer: &mut Vec<f64>) {
+    buffer.clear();
+    for polygon in polygons.iter() {
+        buffer.push(polygon.vertices.len() as f64);
+        buffer.push(polygon.color_r);
+        buffer.push(polygon.color_g);
+        buffer.push(polygon.color_b);
+        for (x, y) in polygon.world_vertices() {
+            buffer.push(x);
+            buffer.push(y);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    boundary::Container, collision::pairs_within, particle::Particle, rectangle::Rectangle,
+};
+
+/// Closest distance, as a fraction of the contact distance, at which forces
+/// are evaluated, so particles spawned on top of each other do not explode.
+const MIN_DISTANCE: f64 = 0.8;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PotentialKind {
+    /// `4ε((σ/r)^12 - (σ/r)^6)` with the minimum at the contact distance.
+    LennardJones = 0,
+    /// `ε((1 - e^(-a(r - r0)))^2 - 1)` with `a = width / r0`.
+    Morse = 1,
+    /// `ε(1 - r/r0)^2` while overlapping, nothing otherwise.
+    SoftRepulsion = 2,
+}
+
+/// Interaction between every pair of particles. Distances are measured
+/// against the contact distance `r0`, the sum of the two radii, so the
+/// radii in the particle buffer keep their meaning.
+#[derive(Clone, Copy, Debug)]
+pub struct PairPotential {
+    pub kind: PotentialKind,
+    /// Depth of the well, or height of the soft repulsion at full overlap.
+    pub epsilon: f64,
+    /// Interaction range in multiples of the contact distance; the potential
+    /// is shifted to zero there.
+    pub cutoff: f64,
+    /// Stiffness of the Morse well.
+    pub width: f64,
+}
+
+impl Default for PairPotential {
+    fn default() -> PairPotential {
+        PairPotential {
+            kind: PotentialKind::LennardJones,
+            epsilon: 0.05,
+            cutoff: 2.5,
+            width: 6.0,
+        }
+    }
+}
+
+impl PairPotential {
+    fn range(&self, contact: f64) -> f64 {
+        match self.kind {
+            PotentialKind::SoftRepulsion => contact,
+            _ => self.cutoff * contact,
+        }
+    }
+
+    /// Unshifted `(force, energy)` at `distance` for a pair whose contact
+    /// distance is `contact`. Positive forces push the pair apart.
+    fn evaluate(&self, distance: f64, contact: f64) -> (f64, f64) {
+        match self.kind {
+            PotentialKind::LennardJones => {
+                let sigma = contact / 2f64.powf(1.0 / 6.0);
+                let s6 = (sigma / distance).powi(6);
+                (
+                    24.0 * self.epsilon * (2.0 * s6 * s6 - s6) / distance,
+                    4.0 * self.epsilon * (s6 * s6 - s6),
+                )
+            }
+            PotentialKind::Morse => {
+                let a = self.width / contact;
+                let e = (-a * (distance - contact)).exp();
+                (
+                    -2.0 * a * self.epsilon * e * (1.0 - e),
+                    self.epsilon * ((1.0 - e).powi(2) - 1.0),
+                )
+            }
+            PotentialKind::SoftRepulsion => {
+                let overlap = 1.0 - distance / contact;
+                (
+                    2.0 * self.epsilon * overlap / contact,
+                    self.epsilon * overlap * overlap,
+                )
+            }
+        }
+    }
+
+    /// `(force, energy)` at `distance`, or `None` beyond the cutoff.
+    pub fn interaction(&self, distance: f64, contact: f64) -> Option<(f64, f64)> {
+        let range = self.range(contact);
+        if distance >= range {
+            return None;
+        }
+
+        let (force, energy) = self.evaluate(distance.max(MIN_DISTANCE * contact), contact);
+        let (_, shift) = self.evaluate(range, contact);
+        Some((force, energy - shift))
+    }
+}
+
+/// Accelerates the particles by the pair forces for one tick and returns the
+/// total potential energy.
+pub fn apply_pair_potential(
+    potential: &PairPotential,
+    particles: &mut [Particle],
+    container: &Container,
+    bounds: &Rectangle,
+) -> f64 {
+    let max_radius = particles
+        .iter()
+        .fold(0.0, |max: f64, particle| max.max(particle.radius));
+    let reach = potential.range(2.0 * max_radius);
+
+    let mut energy = 0.0;
+    for (i, j) in pairs_within(particles, container, bounds, reach) {
+        let (a, b) = (particles[i], particles[j]);
+        if !a.collides_with(&b) {
+            continue;
+        }
+        let (dx, dy) = container.displacement((a.x, a.y), (b.x, b.y), bounds);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance == 0.0 {
+            continue;
+        }
+
+        let (force, pair_energy) = match potential.interaction(distance, a.radius + b.radius) {
+            Some(interaction) => interaction,
+            None => continue,
+        };
+        energy += pair_energy;
+
+        let (nx, ny) = (dx / distance, dy / distance);
+        particles[i].dx += force * nx / a.mass;
+        particles[i].dy += force * ny / a.mass;
+        particles[j].dx -= force * nx / b.mass;
+        particles[j].dy -= force * ny / b.mass;
+    }
+    energy
+}
@@ -1,5 +1,24 @@
 use crate::{particle::Particle, rectangle::Rectangle};
 
+/// Total charge of one sign in a subtree and the point it is centered on.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChargeCluster {
+    pub charge: f64,
+    pub x: f64,
+    pub y: f64,
+}
+
+impl ChargeCluster {
+    fn add(&mut self, charge: f64, x: f64, y: f64) {
+        let total = self.charge + charge;
+        if total != 0.0 {
+            self.x = (self.x * self.charge + x * charge) / total;
+            self.y = (self.y * self.charge + y * charge) / total;
+        }
+        self.charge = total;
+    }
+}
+
 #[derive(Clone)]
 pub struct QuadTree {
     pub boundary: Rectangle,
@@ -10,6 +29,9 @@ pub struct QuadTree {
     pub northwest: Option<Box<QuadTree>>,
     pub southeast: Option<Box<QuadTree>>,
     pub southwest: Option<Box<QuadTree>>,
+    /// Positive and negative charge in this subtree, filled in by
+    /// [`QuadTree::accumulate_charges`].
+    pub charges: [ChargeCluster; 2],
 }
 
 impl QuadTree {
@@ -23,19 +45,19 @@ impl QuadTree {
             northwest: None,
             southeast: None,
             southwest: None,
+            charges: [ChargeCluster::default(); 2],
         }
     }
 
     pub fn subdivide(&mut self) {
-        let x = self.boundary.x;
-        let y = self.boundary.y;
-        let w = self.boundary.w / 2.0;
-        let h = self.boundary.h / 2.0;
+        let (min_x, min_y) = self.boundary.min();
+        let (max_x, max_y) = self.boundary.max();
+        let (x, y) = (self.boundary.x, self.boundary.y);
 
-        let ne = Rectangle::new(x + w, y - h, w, h);
-        let nw = Rectangle::new(x - w, y - h, w, h);
-        let se = Rectangle::new(x + w, y + h, w, h);
-        let sw = Rectangle::new(x - w, y + h, w, h);
+        let ne = Rectangle::from_corners(x, min_y, max_x, y);
+        let nw = Rectangle::from_corners(min_x, min_y, x, y);
+        let se = Rectangle::from_corners(x, y, max_x, max_y);
+        let sw = Rectangle::from_corners(min_x, y, x, max_y);
 
         self.northeast = Some(Box::new(QuadTree::new(ne, self.capacity)));
         self.northwest = Some(Box::new(QuadTree::new(nw, self.capacity)));
@@ -53,44 +75,125 @@ impl QuadTree {
         if self.particles.len() < self.capacity {
             self.particles.push(*particle);
             return true;
-        } else {
-            if !self.is_divided {
-                self.subdivide();
-            }
+        }
 
-            if self.northeast.as_mut().unwrap().insert(particle) {
-                return true;
-            }
-            if self.northwest.as_mut().unwrap().insert(particle) {
-                return true;
-            }
-            if self.southeast.as_mut().unwrap().insert(particle) {
-                return true;
-            }
-            if self.southwest.as_mut().unwrap().insert(particle) {
-                return true;
-            }
+        if !self.is_divided {
+            self.subdivide();
+        }
+
+        if self.northeast.as_mut().unwrap().insert(particle) {
+            return true;
+        }
+        if self.northwest.as_mut().unwrap().insert(particle) {
+            return true;
+        }
+        if self.southeast.as_mut().unwrap().insert(particle) {
+            return true;
+        }
+        if self.southwest.as_mut().unwrap().insert(particle) {
+            return true;
         }
 
-        false
+        // Straddles the split, so no quadrant holds it whole; keep it here
+        // rather than losing it.
+        self.particles.push(*particle);
+        true
     }
 
-    pub fn query(&mut self, range: &mut Rectangle, found: &mut Vec<Particle>) {
-        if !self.boundary.intersects(&range) {
+    pub fn query(&self, range: &Rectangle, found: &mut Vec<Particle>) {
+        if !self.boundary.intersects(range) {
             return;
         }
 
         for particle in &self.particles {
-            if range.contains(&particle) {
+            if range.contains(particle) {
                 found.push(*particle);
             }
         }
 
         if self.is_divided {
-            self.northeast.as_mut().unwrap().query(range, found);
-            self.northwest.as_mut().unwrap().query(range, found);
-            self.southeast.as_mut().unwrap().query(range, found);
-            self.southwest.as_mut().unwrap().query(range, found);
+            self.northeast.as_ref().unwrap().query(range, found);
+            self.northwest.as_ref().unwrap().query(range, found);
+            self.southeast.as_ref().unwrap().query(range, found);
+            self.southwest.as_ref().unwrap().query(range, found);
+        }
+    }
+
+    /// Keeps in `best` the particle whose surface is closest to `(x, y)`
+    /// and no further than `max_distance`, with that distance. Subtrees
+    /// that cannot hold a closer one are skipped, which needs the largest
+    /// radius in the tree.
+    pub fn nearest(
+        &self,
+        x: f64,
+        y: f64,
+        max_distance: f64,
+        max_radius: f64,
+        best: &mut Option<(f64, Particle)>,
+    ) {
+        let (nearest_x, nearest_y) = self.boundary.closest_point(x, y);
+        let bound = ((x - nearest_x).powi(2) + (y - nearest_y).powi(2)).sqrt() - max_radius;
+        let limit = best.map_or(max_distance, |(distance, _)| distance);
+        if bound > limit {
+            return;
+        }
+
+        for particle in &self.particles {
+            let dx = particle.x - x;
+            let dy = particle.y - y;
+            let distance = (dx * dx + dy * dy).sqrt() - particle.radius;
+            let is_closer = match best {
+                Some((closest, _)) => distance < *closest,
+                None => distance <= max_distance,
+            };
+            if is_closer {
+                *best = Some((distance, *particle));
+            }
+        }
+
+        if self.is_divided {
+            self.northeast
+                .as_ref()
+                .unwrap()
+                .nearest(x, y, max_distance, max_radius, best);
+            self.northwest
+                .as_ref()
+                .unwrap()
+                .nearest(x, y, max_distance, max_radius, best);
+            self.southeast
+                .as_ref()
+                .unwrap()
+                .nearest(x, y, max_distance, max_radius, best);
+            self.southwest
+                .as_ref()
+                .unwrap()
+                .nearest(x, y, max_distance, max_radius, best);
+        }
+    }
+
+    /// Sums up the charges of each sign in every subtree. Opposite charges
+    /// are kept apart so a neutral but polarized cluster still has a field.
+    pub fn accumulate_charges(&mut self) {
+        let mut charges = [ChargeCluster::default(); 2];
+
+        for particle in self.particles.iter() {
+            let sign = if particle.charge >= 0.0 { 0 } else { 1 };
+            charges[sign].add(particle.charge, particle.x, particle.y);
         }
+
+        for child in self
+            .northeast
+            .iter_mut()
+            .chain(self.northwest.iter_mut())
+            .chain(self.southeast.iter_mut())
+            .chain(self.southwest.iter_mut())
+        {
+            child.accumulate_charges();
+            for (total, cluster) in charges.iter_mut().zip(child.charges.iter()) {
+                total.add(cluster.charge, cluster.x, cluster.y);
+            }
+        }
+
+        self.charges = charges;
     }
 }
@@ -1,6 +1,7 @@
 use crate::particle::Particle;
 
-#[derive(Clone)]
+/// Axis-aligned bounding box stored as a center point and half extents.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Rectangle {
     pub x: f64,
     pub y: f64,
@@ -13,17 +14,113 @@ impl Rectangle {
         Rectangle { x, y, w, h }
     }
 
-    pub fn contains(&mut self, particle: &Particle) -> bool {
-        particle.x - particle.radius > self.x - self.w
-            && particle.x + particle.radius < self.x + self.w
-            && particle.y - particle.radius > self.y - self.h
-            && particle.y + particle.radius < self.y + self.h
+    /// Builds a rectangle from its top-left and bottom-right corners.
+    pub fn from_corners(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Rectangle {
+        let w = (max_x - min_x).abs() / 2.0;
+        let h = (max_y - min_y).abs() / 2.0;
+        Rectangle::new(min_x.min(max_x) + w, min_y.min(max_y) + h, w, h)
     }
 
-    pub fn intersects(&mut self, range: &Rectangle) -> bool {
-        !(range.x - range.w > self.x + self.w
-            || range.x + range.w < self.x - self.w
-            || range.y - range.h > self.y + self.h
-            || range.y + range.h < self.y - self.h)
+    pub fn min_x(&self) -> f64 {
+        self.x - self.w
+    }
+
+    pub fn min_y(&self) -> f64 {
+        self.y - self.h
+    }
+
+    pub fn max_x(&self) -> f64 {
+        self.x + self.w
+    }
+
+    pub fn max_y(&self) -> f64 {
+        self.y + self.h
+    }
+
+    pub fn min(&self) -> (f64, f64) {
+        (self.min_x(), self.min_y())
+    }
+
+    pub fn max(&self) -> (f64, f64) {
+        (self.max_x(), self.max_y())
+    }
+
+    pub fn width(&self) -> f64 {
+        2.0 * self.w
+    }
+
+    pub fn height(&self) -> f64 {
+        2.0 * self.h
+    }
+
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
+    pub fn contains_point(&self, x: f64, y: f64) -> bool {
+        x >= self.min_x() && x <= self.max_x() && y >= self.min_y() && y <= self.max_y()
+    }
+
+    /// True when the whole disc of `particle` lies strictly inside the rectangle.
+    pub fn contains(&self, particle: &Particle) -> bool {
+        particle.x - particle.radius > self.min_x()
+            && particle.x + particle.radius < self.max_x()
+            && particle.y - particle.radius > self.min_y()
+            && particle.y + particle.radius < self.max_y()
+    }
+
+    pub fn intersects(&self, range: &Rectangle) -> bool {
+        !(range.min_x() > self.max_x()
+            || range.max_x() < self.min_x()
+            || range.min_y() > self.max_y()
+            || range.max_y() < self.min_y())
+    }
+
+    /// True when the circle at `(x, y)` with radius `radius` overlaps the rectangle.
+    pub fn intersects_circle(&self, x: f64, y: f64, radius: f64) -> bool {
+        let (nearest_x, nearest_y) = self.closest_point(x, y);
+        let dx = x - nearest_x;
+        let dy = y - nearest_y;
+        dx * dx + dy * dy <= radius * radius
+    }
+
+    /// Point of the rectangle nearest to `(x, y)`; `(x, y)` itself when inside.
+    pub fn closest_point(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            x.max(self.min_x()).min(self.max_x()),
+            y.max(self.min_y()).min(self.max_y()),
+        )
+    }
+
+    pub fn union(&self, other: &Rectangle) -> Rectangle {
+        Rectangle::from_corners(
+            self.min_x().min(other.min_x()),
+            self.min_y().min(other.min_y()),
+            self.max_x().max(other.max_x()),
+            self.max_y().max(other.max_y()),
+        )
+    }
+
+    pub fn intersection(&self, other: &Rectangle) -> Option<Rectangle> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        Some(Rectangle::from_corners(
+            self.min_x().max(other.min_x()),
+            self.min_y().max(other.min_y()),
+            self.max_x().min(other.max_x()),
+            self.max_y().min(other.max_y()),
+        ))
+    }
+
+    /// Grows the rectangle by `margin` on every side; a negative margin shrinks it.
+    pub fn expand(&self, margin: f64) -> Rectangle {
+        Rectangle::new(
+            self.x,
+            self.y,
+            (self.w + margin).max(0.0),
+            (self.h + margin).max(0.0),
+        )
     }
 }
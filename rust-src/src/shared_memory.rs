@@ -1,8 +1,32 @@
 use wasm_bindgen::prelude::*;
 
 use crate::{
-    particle::Particle,
-    utils::{get_random_integer, update_particle_colors},
+    boundary::{Container, Edge, EdgeMode},
+    collision::{merge_touching_particles, resolve_collision, CollisionMode},
+    constraint::{remap_constraints, solve_constraints, Constraint, ConstraintKind},
+    electromagnetism::Electromagnetism,
+    emitter::{age_particles, Emitter},
+    events::CollisionEvent,
+    force_field::{apply_force_fields, ForceField, ForceKind},
+    fragment::{fragment_impacting_particles, Fragmentation, ScatterDistribution},
+    geometry::points_from_flat,
+    observables::{
+        collision_count_histogram, fit_maxwell_boltzmann, maxwell_boltzmann_curve, speed_histogram,
+        temperature, CollisionStatistics, RadialDistribution, Transport, WallPressure,
+    },
+    obstacle::{collide_particles_with_obstacles, Obstacle},
+    particle::{find_particle, remove_particles, Particle},
+    pbd::{step_position_based, Solver},
+    picking::{Region, SpatialIndex},
+    pointer::Pointer,
+    polygon::{tick_polygons, write_vertex_buffer, Polygon},
+    potential::{apply_pair_potential, PairPotential, PotentialKind},
+    rectangle::Rectangle,
+    thermostat::{Thermostat, ThermostatKind},
+    utils::{
+        find_by_id, get_random_integer, update_particle_colors, ColorMapping, ColorQuantity,
+        Colormap,
+    },
 };
 
 #[wasm_bindgen]
@@ -10,6 +34,36 @@ pub struct Universe {
     pub width: f64,
     pub height: f64,
     particles: Vec<Particle>,
+    next_id: u32,
+    polygons: Vec<Polygon>,
+    polygon_vertices: Vec<f64>,
+    obstacles: Vec<Obstacle>,
+    container: Container,
+    absorbed_counts: [u32; 4],
+    emitters: Vec<Emitter>,
+    next_emitter_id: u32,
+    force_fields: Vec<ForceField>,
+    next_force_id: u32,
+    pointer: Pointer,
+    constraints: Vec<Constraint>,
+    next_constraint_id: u32,
+    pub constraint_iterations: u32,
+    pub solver: Solver,
+    pub solver_iterations: u32,
+    pub collision_mode: CollisionMode,
+    fragmentation: Fragmentation,
+    pair_potential: PairPotential,
+    potential_energy: f64,
+    electromagnetism: Electromagnetism,
+    thermostat: Thermostat,
+    wall_pressure: WallPressure,
+    radial_distribution: Option<RadialDistribution>,
+    transport: Option<Transport>,
+    collision_statistics: Option<CollisionStatistics>,
+    /// Built on the first picking query after the particles change.
+    spatial_index: Option<SpatialIndex>,
+    collision_events: Option<Vec<CollisionEvent>>,
+    color_mapping: ColorMapping,
     pub coefficient_of_restitution: f64,
     pub is_wall_elastic: bool,
 }
@@ -22,6 +76,35 @@ impl Universe {
             width,
             height,
             particles: Vec::new(),
+            next_id: 0,
+            polygons: Vec::new(),
+            polygon_vertices: Vec::new(),
+            obstacles: Vec::new(),
+            container: Container::rectangle(),
+            absorbed_counts: [0; 4],
+            emitters: Vec::new(),
+            next_emitter_id: 0,
+            force_fields: Vec::new(),
+            next_force_id: 0,
+            pointer: Pointer::new(),
+            constraints: Vec::new(),
+            next_constraint_id: 0,
+            constraint_iterations: 4,
+            solver: Solver::Impulse,
+            solver_iterations: 8,
+            collision_mode: CollisionMode::Bounce,
+            fragmentation: Fragmentation::default(),
+            pair_potential: PairPotential::default(),
+            potential_energy: 0.0,
+            electromagnetism: Electromagnetism::default(),
+            thermostat: Thermostat::default(),
+            wall_pressure: WallPressure::default(),
+            radial_distribution: None,
+            transport: None,
+            collision_statistics: None,
+            spatial_index: None,
+            collision_events: None,
+            color_mapping: ColorMapping::default(),
             coefficient_of_restitution: 1.0,
             is_wall_elastic: true,
         }
@@ -29,18 +112,886 @@ impl Universe {
 
     #[wasm_bindgen]
     pub fn insert_particle(&mut self, x: f64, y: f64, dx: f64, dy: f64, radius: f64) {
-        let particle = Particle {
+        self.particles.push(Particle::new(x, y, dx, dy, radius));
+        self.spatial_index = None;
+        self.assign_ids_from(self.particles.len() - 1);
+    }
+
+    #[wasm_bindgen]
+    pub fn insert_charged_particle(
+        &mut self,
+        x: f64,
+        y: f64,
+        dx: f64,
+        dy: f64,
+        radius: f64,
+        charge: f64,
+    ) {
+        let mut particle = Particle::new(x, y, dx, dy, radius);
+        particle.charge = charge;
+        self.particles.push(particle);
+        self.spatial_index = None;
+        self.assign_ids_from(self.particles.len() - 1);
+    }
+
+    /// Index of the particle with `id` in the particle buffer, which changes
+    /// as particles before it are removed.
+    #[wasm_bindgen]
+    pub fn get_particle_index(&self, id: u32) -> Option<usize> {
+        find_particle(&self.particles, id as f64)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_particle(&self, id: u32) -> Option<Particle> {
+        self.get_particle_index(id)
+            .map(|index| self.particles[index])
+    }
+
+    #[wasm_bindgen]
+    pub fn remove_particle(&mut self, id: u32) -> bool {
+        match self.get_particle_index(id) {
+            Some(index) => {
+                self.remove_particles_at(&[index]);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ids of the particles whose disc covers `(x, y)`.
+    #[wasm_bindgen]
+    pub fn get_particles_at_point(&mut self, x: f64, y: f64) -> Vec<u32> {
+        self.spatial_index().query(&Region::Point { x, y })
+    }
+
+    /// Ids of the particles centered inside the rectangle with corners
+    /// `(x1, y1)` and `(x2, y2)`.
+    #[wasm_bindgen]
+    pub fn get_particles_in_rectangle(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<u32> {
+        self.spatial_index()
+            .query(&Region::Rectangle(Rectangle::from_corners(x1, y1, x2, y2)))
+    }
+
+    /// Ids of the particles centered inside the circle at `(x, y)`.
+    #[wasm_bindgen]
+    pub fn get_particles_in_circle(&mut self, x: f64, y: f64, radius: f64) -> Vec<u32> {
+        self.spatial_index().query(&Region::Circle { x, y, radius })
+    }
+
+    /// Id of the particle whose surface is closest to `(x, y)`, if it lies
+    /// within `max_distance`.
+    #[wasm_bindgen]
+    pub fn get_nearest_particle(&mut self, x: f64, y: f64, max_distance: f64) -> Option<u32> {
+        self.spatial_index().nearest(x, y, max_distance)
+    }
+
+    #[wasm_bindgen]
+    pub fn set_particle_charge(&mut self, id: u32, charge: f64) -> bool {
+        match self.get_particle_index(id) {
+            Some(index) => {
+                self.particles[index].charge = charge;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Puts the particle with `id` in `group`, from 0 to 31, colliding only
+    /// with particles whose group bit is set in `mask` and whose own mask
+    /// includes `group`.
+    #[wasm_bindgen]
+    pub fn set_particle_collision_group(&mut self, id: u32, group: u32, mask: u32) -> bool {
+        match self.get_particle_index(id) {
+            Some(index) if group < 32 => {
+                let particle = &mut self.particles[index];
+                particle.group = group as f64;
+                particle.collision_mask = mask as f64;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Colors the particle with `id`; the color only lasts while colors are
+    /// fixed.
+    #[wasm_bindgen]
+    pub fn set_particle_color(&mut self, id: u32, r: f64, g: f64, b: f64) -> bool {
+        match self.get_particle_index(id) {
+            Some(index) => {
+                let particle = &mut self.particles[index];
+                particle.color_r = r;
+                particle.color_g = g;
+                particle.color_b = b;
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn set_colormap(&mut self, colormap: Colormap, quantity: ColorQuantity) {
+        self.color_mapping.colormap = colormap;
+        self.color_mapping.quantity = quantity;
+        self.color_mapping.fixed = false;
+    }
+
+    /// Maps `min` and `max` to the ends of the colormap.
+    #[wasm_bindgen]
+    pub fn set_color_range(&mut self, min: f64, max: f64) {
+        self.color_mapping.min = min;
+        self.color_mapping.max = max;
+        self.color_mapping.auto_range = false;
+    }
+
+    /// Stretches the colormap over the values of each tick.
+    #[wasm_bindgen]
+    pub fn set_auto_color_range(&mut self) {
+        self.color_mapping.auto_range = true;
+    }
+
+    /// Stops recoloring particles each tick, keeping the colors they have.
+    #[wasm_bindgen]
+    pub fn set_fixed_colors(&mut self, fixed: bool) {
+        self.color_mapping.fixed = fixed;
+    }
+
+    /// Adds a rigid body shaped like the convex hull of `vertices`, a flat
+    /// `[x0, y0, x1, y1, ...]` list in world coordinates.
+    #[wasm_bindgen]
+    pub fn insert_polygon(&mut self, vertices: Vec<f64>, dx: f64, dy: f64) -> bool {
+        match Polygon::new(&points_from_flat(&vertices), dx, dy) {
+            Some(polygon) => {
+                self.polygons.push(polygon);
+                write_vertex_buffer(&self.polygons, &mut self.polygon_vertices);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn insert_box(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        dx: f64,
+        dy: f64,
+    ) -> bool {
+        match Polygon::new_box(x, y, width, height, dx, dy) {
+            Some(polygon) => {
+                self.polygons.push(polygon);
+                write_vertex_buffer(&self.polygons, &mut self.polygon_vertices);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_polygons(&mut self) {
+        self.polygons.clear();
+        self.polygon_vertices.clear();
+    }
+
+    #[wasm_bindgen]
+    pub fn add_segment(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        self.obstacles.push(Obstacle::segment(x1, y1, x2, y2));
+    }
+
+    #[wasm_bindgen]
+    pub fn add_capsule(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, radius: f64) {
+        self.obstacles
+            .push(Obstacle::capsule(x1, y1, x2, y2, radius));
+    }
+
+    /// Adds a chain of capsules through `points`, a flat `[x0, y0, x1, y1, ...]` list.
+    #[wasm_bindgen]
+    pub fn add_polyline(&mut self, points: Vec<f64>, radius: f64) {
+        self.obstacles
+            .extend(Obstacle::polyline(&points_from_flat(&points), radius));
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_obstacles(&mut self) {
+        self.obstacles.clear();
+    }
+
+    /// Obstacles are laid out as `[x1, y1, x2, y2, radius]` per entry.
+    #[wasm_bindgen]
+    pub fn get_obstacles_ptr(&self) -> *const Obstacle {
+        self.obstacles.as_ptr()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_obstacles_len(&self) -> usize {
+        self.obstacles.len()
+    }
+
+    /// Restores the default `[0, width] × [0, height]` container.
+    #[wasm_bindgen]
+    pub fn set_rectangular_container(&mut self) {
+        self.container = Container::rectangle();
+    }
+
+    #[wasm_bindgen]
+    pub fn set_circular_container(&mut self, x: f64, y: f64, radius: f64) {
+        self.container = Container::Circle { x, y, radius };
+    }
+
+    /// Makes opposite edges of the rectangular container periodic, so particles
+    /// leaving one side re-enter on the other.
+    #[wasm_bindgen]
+    pub fn set_periodic_boundaries(&mut self, periodic_x: bool, periodic_y: bool) {
+        self.container.set_periodic(periodic_x, periodic_y);
+    }
+
+    /// Sets how one side of the rectangular container treats particles and
+    /// polygons; an absorbing side removes them from the universe and counts
+    /// them.
+    #[wasm_bindgen]
+    pub fn set_edge_mode(&mut self, edge: Edge, mode: EdgeMode) {
+        self.container.set_edge_mode(edge, mode);
+    }
+
+    /// Number of particles and polygons removed by absorbing sides since the
+    /// last reset.
+    #[wasm_bindgen]
+    pub fn get_absorbed_count(&self) -> u32 {
+        self.absorbed_counts.iter().sum()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_absorbed_count_at(&self, edge: Edge) -> u32 {
+        self.absorbed_counts[edge as usize]
+    }
+
+    #[wasm_bindgen]
+    pub fn reset_absorbed_counts(&mut self) {
+        self.absorbed_counts = [0; 4];
+    }
+
+    /// Uses the simple polygon through `vertices`, a flat `[x0, y0, x1, y1, ...]`
+    /// list, as the container. Returns `false` and keeps the current container
+    /// when fewer than three vertices are given.
+    #[wasm_bindgen]
+    pub fn set_polygon_container(&mut self, vertices: Vec<f64>) -> bool {
+        let vertices = points_from_flat(&vertices);
+        if vertices.len() < 3 {
+            return false;
+        }
+
+        self.container = Container::Polygon { vertices };
+        true
+    }
+
+    /// Attaches `emitter` to the universe and returns its id, which stays
+    /// valid as other emitters are removed.
+    #[wasm_bindgen]
+    pub fn add_emitter(&mut self, mut emitter: Emitter) -> u32 {
+        let id = self.next_emitter_id;
+        self.next_emitter_id += 1;
+        emitter.set_id(id);
+        self.emitters.push(emitter);
+        id
+    }
+
+    #[wasm_bindgen]
+    pub fn get_emitter(&self, id: u32) -> Option<Emitter> {
+        find_by_id(&self.emitters, id, Emitter::get_id).map(|index| self.emitters[index].clone())
+    }
+
+    /// Replaces the emitter with `id`, keeping its emitted count.
+    #[wasm_bindgen]
+    pub fn update_emitter(&mut self, id: u32, mut emitter: Emitter) -> bool {
+        match find_by_id(&self.emitters, id, Emitter::get_id) {
+            Some(index) => {
+                emitter.set_id(id);
+                emitter.set_emitted_count(self.emitters[index].get_emitted_count());
+                self.emitters[index] = emitter;
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn remove_emitter(&mut self, id: u32) -> bool {
+        match find_by_id(&self.emitters, id, Emitter::get_id) {
+            Some(index) => {
+                self.emitters.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_emitters(&mut self) {
+        self.emitters.clear();
+    }
+
+    /// Adds a force field and returns its id, which stays valid as other
+    /// fields are removed.
+    fn add_force(&mut self, kind: ForceKind) -> u32 {
+        let id = self.next_force_id;
+        self.next_force_id += 1;
+        self.force_fields.push(ForceField::new(id, kind));
+        id
+    }
+
+    #[wasm_bindgen]
+    pub fn add_gravity(&mut self, gx: f64, gy: f64) -> u32 {
+        self.add_force(ForceKind::Gravity { gx, gy })
+    }
+
+    #[wasm_bindgen]
+    pub fn add_linear_drag(&mut self, coefficient: f64) -> u32 {
+        self.add_force(ForceKind::LinearDrag { coefficient })
+    }
+
+    #[wasm_bindgen]
+    pub fn add_quadratic_drag(&mut self, coefficient: f64) -> u32 {
+        self.add_force(ForceKind::QuadraticDrag { coefficient })
+    }
+
+    #[wasm_bindgen]
+    pub fn add_wind(&mut self, vx: f64, vy: f64, coefficient: f64) -> u32 {
+        self.add_force(ForceKind::Wind {
+            vx,
+            vy,
+            coefficient,
+        })
+    }
+
+    /// Attracts towards `(x, y)` when `strength` is positive, repels otherwise.
+    #[wasm_bindgen]
+    pub fn add_point_force(
+        &mut self,
+        x: f64,
+        y: f64,
+        strength: f64,
+        falloff: f64,
+        softening: f64,
+    ) -> u32 {
+        self.add_force(ForceKind::Point {
             x,
             y,
-            dy,
-            dx,
-            radius,
-            color_r: 255.0,
-            color_g: 255.0,
-            color_b: 255.0,
+            strength,
+            falloff,
+            softening,
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn add_vortex(
+        &mut self,
+        x: f64,
+        y: f64,
+        strength: f64,
+        falloff: f64,
+        softening: f64,
+    ) -> u32 {
+        self.add_force(ForceKind::Vortex {
+            x,
+            y,
+            strength,
+            falloff,
+            softening,
+        })
+    }
+
+    /// Limits the force with `id` to the rectangle between the two corners.
+    #[wasm_bindgen]
+    pub fn set_force_region(&mut self, id: u32, x1: f64, y1: f64, x2: f64, y2: f64) -> bool {
+        match find_by_id(&self.force_fields, id, |field| field.id) {
+            Some(index) => {
+                self.force_fields[index].region = Some(Rectangle::from_corners(x1, y1, x2, y2));
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_force_region(&mut self, id: u32) -> bool {
+        match find_by_id(&self.force_fields, id, |field| field.id) {
+            Some(index) => {
+                self.force_fields[index].region = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn remove_force(&mut self, id: u32) -> bool {
+        match find_by_id(&self.force_fields, id, |field| field.id) {
+            Some(index) => {
+                self.force_fields.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_forces(&mut self) {
+        self.force_fields.clear();
+    }
+
+    /// Moves the pointer that the pointer force and the drag spring follow.
+    #[wasm_bindgen]
+    pub fn set_pointer(&mut self, x: f64, y: f64) {
+        self.pointer.x = x;
+        self.pointer.y = y;
+    }
+
+    /// Pulls particles within `radius` of the pointer towards it, or pushes
+    /// them away for a negative `strength`, until cleared.
+    #[wasm_bindgen]
+    pub fn set_pointer_force(&mut self, strength: f64, radius: f64) {
+        self.pointer.strength = strength;
+        self.pointer.radius = radius;
+        self.pointer.is_force_active = true;
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_pointer_force(&mut self) {
+        self.pointer.is_force_active = false;
+    }
+
+    /// Attaches the particle nearest to `(x, y)` to the pointer with a spring
+    /// and returns its id, or nothing if none is within `max_distance`.
+    #[wasm_bindgen]
+    pub fn grab_nearest_particle(&mut self, x: f64, y: f64, max_distance: f64) -> Option<u32> {
+        self.set_pointer(x, y);
+        let id = self.get_nearest_particle(x, y, max_distance);
+        self.pointer.grabbed = id.and_then(|id| self.get_particle_index(id));
+        id
+    }
+
+    #[wasm_bindgen]
+    pub fn release_particle(&mut self) {
+        self.pointer.grabbed = None;
+    }
+
+    #[wasm_bindgen]
+    pub fn set_drag_spring(&mut self, stiffness: f64, damping: f64) {
+        self.pointer.stiffness = stiffness;
+        self.pointer.damping = damping;
+    }
+
+    /// Gives fresh ids to the particles from `start` on, which were just
+    /// appended.
+    fn assign_ids_from(&mut self, start: usize) {
+        for particle in self.particles[start..].iter_mut() {
+            particle.id = self.next_id as f64;
+            self.next_id += 1;
+        }
+    }
+
+    fn spatial_index(&mut self) -> &SpatialIndex {
+        let particles = &self.particles;
+        self.spatial_index
+            .get_or_insert_with(|| SpatialIndex::new(particles))
+    }
+
+    /// Removes the particles at the sorted `indices` together with whatever
+    /// refers to them by index.
+    fn remove_particles_at(&mut self, indices: &[usize]) {
+        remove_particles(&mut self.particles, indices);
+        self.spatial_index = None;
+        remap_constraints(&mut self.constraints, indices);
+        self.pointer.remap(indices);
+        if let Some(transport) = self.transport.as_mut() {
+            transport.remap(indices);
+        }
+    }
+
+    /// Links the particles with ids `a` and `b`, using their current
+    /// distance when `length` is not positive. Returns the constraint id,
+    /// which stays valid until the constraint or one of its particles is
+    /// removed.
+    fn add_constraint(&mut self, a: u32, b: u32, length: f64, kind: ConstraintKind) -> Option<u32> {
+        let (a, b) = match (self.get_particle_index(a), self.get_particle_index(b)) {
+            (Some(a), Some(b)) if a != b => (a, b),
+            _ => return None,
         };
 
-        self.particles.push(particle);
+        let length = if length > 0.0 {
+            length
+        } else {
+            let (dx, dy) = (
+                self.particles[b].x - self.particles[a].x,
+                self.particles[b].y - self.particles[a].y,
+            );
+            (dx * dx + dy * dy).sqrt()
+        };
+
+        let id = self.next_constraint_id;
+        self.next_constraint_id += 1;
+        self.constraints.push(Constraint {
+            id,
+            a,
+            b,
+            length,
+            kind,
+        });
+        Some(id)
+    }
+
+    #[wasm_bindgen]
+    pub fn add_rigid_link(&mut self, a: u32, b: u32, length: f64) -> Option<u32> {
+        self.add_constraint(a, b, length, ConstraintKind::Rigid)
+    }
+
+    #[wasm_bindgen]
+    pub fn add_spring(
+        &mut self,
+        a: u32,
+        b: u32,
+        length: f64,
+        stiffness: f64,
+        damping: f64,
+    ) -> Option<u32> {
+        self.add_constraint(a, b, length, ConstraintKind::Spring { stiffness, damping })
+    }
+
+    #[wasm_bindgen]
+    pub fn add_rope(&mut self, a: u32, b: u32, length: f64) -> Option<u32> {
+        self.add_constraint(a, b, length, ConstraintKind::Rope)
+    }
+
+    #[wasm_bindgen]
+    pub fn remove_constraint(&mut self, id: u32) -> bool {
+        match find_by_id(&self.constraints, id, |constraint| constraint.id) {
+            Some(index) => {
+                self.constraints.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_constraints(&mut self) {
+        self.constraints.clear();
+    }
+
+    /// Particle ids of every constraint as `[a0, b0, a1, b1, ...]`.
+    #[wasm_bindgen]
+    pub fn get_constraint_pairs(&self) -> Vec<u32> {
+        self.constraints
+            .iter()
+            .flat_map(|constraint| {
+                [
+                    self.particles[constraint.a].id as u32,
+                    self.particles[constraint.b].id as u32,
+                ]
+            })
+            .collect()
+    }
+
+    /// Id of every constraint, in the same order as the pairs.
+    #[wasm_bindgen]
+    pub fn get_constraint_ids(&self) -> Vec<u32> {
+        self.constraints
+            .iter()
+            .map(|constraint| constraint.id)
+            .collect()
+    }
+
+    #[wasm_bindgen]
+    pub fn set_constraint_iterations(&mut self, iterations: u32) {
+        self.constraint_iterations = iterations;
+    }
+
+    #[wasm_bindgen]
+    pub fn set_solver(&mut self, solver: Solver) {
+        self.solver = solver;
+    }
+
+    /// Projection passes per tick for the position-based solver.
+    #[wasm_bindgen]
+    pub fn set_solver_iterations(&mut self, iterations: u32) {
+        self.solver_iterations = iterations;
+    }
+
+    #[wasm_bindgen]
+    pub fn set_collision_mode(&mut self, mode: CollisionMode) {
+        self.collision_mode = mode;
+    }
+
+    /// Particles shatter into `pieces` fragments when they hit each other
+    /// with more than `threshold` energy in fragment mode.
+    #[wasm_bindgen]
+    pub fn set_fragmentation(
+        &mut self,
+        threshold: f64,
+        pieces: u32,
+        min_radius: f64,
+        scatter: f64,
+        distribution: ScatterDistribution,
+    ) {
+        self.fragmentation = Fragmentation {
+            threshold,
+            pieces,
+            min_radius,
+            scatter,
+            distribution,
+        };
+    }
+
+    /// Pair potential used in potential mode. `cutoff` is in multiples of
+    /// the contact distance and `width` only matters for the Morse potential.
+    #[wasm_bindgen]
+    pub fn set_pair_potential(
+        &mut self,
+        kind: PotentialKind,
+        epsilon: f64,
+        cutoff: f64,
+        width: f64,
+    ) {
+        self.pair_potential = PairPotential {
+            kind,
+            epsilon,
+            cutoff,
+            width,
+        };
+    }
+
+    /// Total pair potential energy as of the last tick.
+    #[wasm_bindgen]
+    pub fn get_potential_energy(&self) -> f64 {
+        self.potential_energy
+    }
+
+    /// `cutoff` of zero lets every pair of charges interact. Rejects a
+    /// `softening` that is not positive, which would make coincident charges
+    /// blow up.
+    #[wasm_bindgen]
+    pub fn set_coulomb(&mut self, constant: f64, softening: f64, cutoff: f64) -> bool {
+        if softening.is_nan() || softening <= 0.0 {
+            return false;
+        }
+
+        self.electromagnetism.coulomb_constant = constant;
+        self.electromagnetism.softening = softening;
+        self.electromagnetism.cutoff = cutoff;
+        true
+    }
+
+    /// Uniform field out of the screen; zero switches it off.
+    #[wasm_bindgen]
+    pub fn set_magnetic_field(&mut self, strength: f64) {
+        self.electromagnetism.magnetic_field = strength;
+    }
+
+    /// `coupling` is the relaxation rate for Berendsen, the per-tick
+    /// collision probability for Andersen and the friction for Langevin,
+    /// each between zero and one.
+    #[wasm_bindgen]
+    pub fn set_thermostat(&mut self, kind: ThermostatKind, coupling: f64) {
+        self.thermostat.kind = kind;
+        self.thermostat.coupling = coupling;
+    }
+
+    #[wasm_bindgen]
+    pub fn set_target_temperature(&mut self, temperature: f64) {
+        self.thermostat.target_temperature = temperature;
+    }
+
+    /// Mean kinetic energy per particle.
+    #[wasm_bindgen]
+    pub fn get_temperature(&self) -> f64 {
+        temperature(&self.particles)
+    }
+
+    /// Momentum given to the container walls per tick and unit length of
+    /// wall, averaged since the last reset.
+    #[wasm_bindgen]
+    pub fn get_wall_pressure(&self) -> f64 {
+        let bounds = Rectangle::from_corners(0.0, 0.0, self.width, self.height);
+        self.wall_pressure
+            .pressure(self.container.wall_length(&bounds))
+    }
+
+    #[wasm_bindgen]
+    pub fn reset_wall_pressure(&mut self) {
+        self.wall_pressure.reset();
+    }
+
+    #[wasm_bindgen]
+    pub fn get_container_area(&self) -> f64 {
+        let bounds = Rectangle::from_corners(0.0, 0.0, self.width, self.height);
+        self.container.area(&bounds)
+    }
+
+    /// Probability density of particle speeds in `bins` bins up to `max_speed`.
+    #[wasm_bindgen]
+    pub fn get_speed_histogram(&self, bins: usize, max_speed: f64) -> Vec<f64> {
+        speed_histogram(&self.particles, bins, max_speed)
+    }
+
+    /// Fitted Maxwell-Boltzmann density at the centers of the same bins.
+    #[wasm_bindgen]
+    pub fn get_maxwell_boltzmann_curve(&self, bins: usize, max_speed: f64) -> Vec<f64> {
+        maxwell_boltzmann_curve(&self.particles, bins, max_speed)
+    }
+
+    /// Temperature of the Maxwell-Boltzmann distribution that best fits the
+    /// current speeds.
+    #[wasm_bindgen]
+    pub fn get_maxwell_boltzmann_temperature(&self) -> f64 {
+        fit_maxwell_boltzmann(&self.particles).1
+    }
+
+    /// Starts averaging `g(r)` over the following ticks, discarding any
+    /// earlier samples.
+    #[wasm_bindgen]
+    pub fn start_radial_distribution(&mut self, bin_width: f64, max_radius: f64) {
+        self.radial_distribution = Some(RadialDistribution::new(bin_width, max_radius));
+    }
+
+    #[wasm_bindgen]
+    pub fn stop_radial_distribution(&mut self) {
+        self.radial_distribution = None;
+    }
+
+    /// `g(r)` per bin of the width passed to `start_radial_distribution`.
+    #[wasm_bindgen]
+    pub fn get_radial_distribution(&self) -> Vec<f64> {
+        self.radial_distribution
+            .as_ref()
+            .map_or_else(Vec::new, RadialDistribution::values)
+    }
+
+    /// `S(k)` at the centers of `bins` equal bins up to `max_wavenumber`.
+    #[wasm_bindgen]
+    pub fn get_structure_factor(&self, max_wavenumber: f64, bins: usize) -> Vec<f64> {
+        self.radial_distribution
+            .as_ref()
+            .map_or_else(Vec::new, |rdf| rdf.structure_factor(max_wavenumber, bins))
+    }
+
+    /// Starts tracking displacements and velocity correlations over lags of
+    /// up to `window` ticks, at most `MAX_BINS`, with a new time origin
+    /// every `interval` ticks.
+    #[wasm_bindgen]
+    pub fn start_transport(&mut self, window: usize, interval: usize) {
+        self.transport = Some(Transport::new(window, interval));
+    }
+
+    #[wasm_bindgen]
+    pub fn stop_transport(&mut self) {
+        self.transport = None;
+    }
+
+    /// Mean squared displacement of the unwrapped positions per lag in ticks.
+    #[wasm_bindgen]
+    pub fn get_mean_squared_displacement(&self) -> Vec<f64> {
+        self.transport
+            .as_ref()
+            .map_or_else(Vec::new, Transport::mean_squared_displacement)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_velocity_autocorrelation(&self) -> Vec<f64> {
+        self.transport
+            .as_ref()
+            .map_or_else(Vec::new, Transport::velocity_autocorrelation)
+    }
+
+    /// Diffusion coefficient from the slope of the mean squared displacement.
+    #[wasm_bindgen]
+    pub fn get_diffusion_coefficient(&self) -> f64 {
+        self.transport
+            .as_ref()
+            .map_or(0.0, Transport::diffusion_from_displacement)
+    }
+
+    /// Diffusion coefficient from the integral of the velocity autocorrelation.
+    #[wasm_bindgen]
+    pub fn get_green_kubo_diffusion_coefficient(&self) -> f64 {
+        self.transport
+            .as_ref()
+            .map_or(0.0, Transport::diffusion_from_autocorrelation)
+    }
+
+    /// Starts counting collisions, from zero for every particle, and
+    /// collecting free paths into `bins` bins up to `max_free_path`. Only
+    /// the impulse solver records collisions.
+    #[wasm_bindgen]
+    pub fn start_collision_statistics(&mut self, bins: usize, max_free_path: f64) {
+        for particle in self.particles.iter_mut() {
+            particle.collision_count = 0.0;
+            particle.free_path = 0.0;
+        }
+        self.collision_statistics = Some(CollisionStatistics::new(bins, max_free_path));
+    }
+
+    #[wasm_bindgen]
+    pub fn stop_collision_statistics(&mut self) {
+        self.collision_statistics = None;
+    }
+
+    #[wasm_bindgen]
+    pub fn get_mean_free_path(&self) -> f64 {
+        self.collision_statistics
+            .as_ref()
+            .map_or(0.0, CollisionStatistics::mean_free_path)
+    }
+
+    /// Collisions per particle per tick.
+    #[wasm_bindgen]
+    pub fn get_collision_rate(&self) -> f64 {
+        self.collision_statistics
+            .as_ref()
+            .map_or(0.0, CollisionStatistics::collision_rate)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_free_path_histogram(&self) -> Vec<f64> {
+        self.collision_statistics
+            .as_ref()
+            .map_or_else(Vec::new, CollisionStatistics::free_path_histogram)
+    }
+
+    /// Fraction of particles with each collision count up to `max_count`.
+    #[wasm_bindgen]
+    pub fn get_collision_count_histogram(&self, max_count: usize) -> Vec<f64> {
+        collision_count_histogram(&self.particles, max_count)
+    }
+
+    /// Starts logging every collision resolved during each tick, one event
+    /// per particle pair and per wall bounce.
+    #[wasm_bindgen]
+    pub fn start_collision_events(&mut self) {
+        self.collision_events = Some(Vec::new());
+    }
+
+    #[wasm_bindgen]
+    pub fn stop_collision_events(&mut self) {
+        self.collision_events = None;
+    }
+
+    /// Events of the last tick are laid out as `[id, other_id, x, y,
+    /// normal_x, normal_y, relative_speed, impulse]` per entry, with an
+    /// `other_id` of -1 for the walls.
+    #[wasm_bindgen]
+    pub fn get_collision_events_ptr(&self) -> *const CollisionEvent {
+        self.collision_events
+            .as_ref()
+            .map_or(std::ptr::null(), |events| events.as_ptr())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_collision_events_len(&self) -> usize {
+        self.collision_events
+            .as_ref()
+            .map_or(0, |events| events.len())
     }
 
     #[wasm_bindgen]
@@ -60,12 +1011,9 @@ impl Universe {
 
     #[wasm_bindgen]
     pub fn decrease_particles(&mut self, count: i32) {
-        if (count as usize) >= self.particles.len() {
-            self.particles = vec![];
-            return;
-        }
-
-        self.particles = self.particles.split_off(count as usize);
+        let count = (count.max(0) as usize).min(self.particles.len());
+        let removed: Vec<usize> = (0..count).collect();
+        self.remove_particles_at(&removed);
     }
 
     #[wasm_bindgen]
@@ -93,6 +1041,22 @@ impl Universe {
         self.particles.len()
     }
 
+    #[wasm_bindgen]
+    pub fn get_polygons_len(&self) -> usize {
+        self.polygons.len()
+    }
+
+    /// See [`write_vertex_buffer`] for the record layout.
+    #[wasm_bindgen]
+    pub fn get_polygon_vertices_ptr(&self) -> *const f64 {
+        self.polygon_vertices.as_ptr()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_polygon_vertices_len(&self) -> usize {
+        self.polygon_vertices.len()
+    }
+
     #[wasm_bindgen]
     pub fn set_coefficient_of_restitution(&mut self, new_coefficient: f64) {
         self.coefficient_of_restitution = new_coefficient;
@@ -105,87 +1069,355 @@ impl Universe {
 
     #[wasm_bindgen]
     pub fn tick(&mut self) {
-        for i in 0..self.particles.len() {
-            let (first, rest) = self.particles.split_at_mut(i + 1);
-            let circle = first.last_mut().unwrap();
-
-            circle.x += circle.dx;
-            circle.y += circle.dy;
-
-            for other_circle in rest.iter_mut() {
-                let dx = circle.x - other_circle.x;
-                let dy = circle.y - other_circle.y;
-                let distance = (dx * dx + dy * dy).sqrt();
-
-                if distance < circle.radius + other_circle.radius {
-                    let angle = dy.atan2(dx);
-                    let sin = angle.sin();
-                    let cos = angle.cos();
-
-                    let v1 = (
-                        cos * circle.dx + sin * circle.dy,
-                        cos * circle.dy - sin * circle.dx,
-                    );
-                    let v2 = (
-                        cos * other_circle.dx + sin * other_circle.dy,
-                        cos * other_circle.dy - sin * other_circle.dx,
-                    );
+        self.spatial_index = None;
+        if let Some(events) = self.collision_events.as_mut() {
+            events.clear();
+        }
 
-                    let m1 = circle.radius;
-                    let m2 = other_circle.radius;
+        // Age first so a new particle is around for its whole lifetime.
+        let expired = age_particles(&mut self.particles);
+        self.remove_particles_at(&expired);
+        let count = self.particles.len();
+        for emitter in self.emitters.iter_mut() {
+            emitter.emit(&mut self.particles);
+        }
+        self.assign_ids_from(count);
+
+        apply_force_fields(&self.force_fields, &mut self.particles, &mut self.polygons);
+        self.pointer.apply(&mut self.particles);
+
+        let bounds = Rectangle::from_corners(0.0, 0.0, self.width, self.height);
+        let wall_restitution = if self.is_wall_elastic {
+            1.0
+        } else {
+            self.coefficient_of_restitution
+        };
+
+        self.electromagnetism
+            .apply_coulomb_pairwise(&mut self.particles, &self.container, &bounds);
+        self.electromagnetism
+            .apply_magnetic_field(&mut self.particles);
+
+        if self.collision_mode == CollisionMode::Fragment {
+            let count = self.particles.len();
+            let shattered = fragment_impacting_particles(
+                &mut self.particles,
+                &self.container,
+                &bounds,
+                &self.fragmentation,
+            );
+            self.assign_ids_from(count);
+            self.remove_particles_at(&shattered);
+        }
 
-                    let mut v1_final = (((m1 - m2) * v1.0 + 2.0 * m2 * v2.0) / (m1 + m2), v1.1);
-                    let mut v2_final = (((m2 - m1) * v2.0 + 2.0 * m1 * v1.0) / (m1 + m2), v2.1);
+        self.potential_energy = if self.collision_mode == CollisionMode::Potential {
+            apply_pair_potential(
+                &self.pair_potential,
+                &mut self.particles,
+                &self.container,
+                &bounds,
+            )
+        } else {
+            0.0
+        };
 
-                    v1_final = (v1_final.0 * self.coefficient_of_restitution, v1_final.1);
-                    v2_final = (v2_final.0 * self.coefficient_of_restitution, v2_final.1);
+        let mut absorbed = Vec::new();
 
-                    circle.dx = cos * v1_final.0 - sin * v1_final.1;
-                    circle.dy = cos * v1_final.1 + sin * v1_final.0;
-                    other_circle.dx = cos * v2_final.0 - sin * v2_final.1;
-                    other_circle.dy = cos * v2_final.1 + sin * v2_final.0;
+        if self.solver == Solver::PositionBased {
+            let wall_impulse = step_position_based(
+                &mut self.particles,
+                &self.constraints,
+                &self.obstacles,
+                &self.container,
+                &bounds,
+                self.solver_iterations,
+                self.coefficient_of_restitution,
+                wall_restitution,
+                self.collision_events.as_mut(),
+            );
+            self.wall_pressure.add_impulse(wall_impulse);
 
-                    let overlap = (circle.radius + other_circle.radius - distance) / 2.0;
-                    circle.x += cos * overlap;
-                    circle.y += sin * overlap;
-                    other_circle.x -= cos * overlap;
-                    other_circle.y -= sin * overlap;
+            for (i, particle) in self.particles.iter_mut().enumerate() {
+                let velocity = (particle.dx, particle.dy);
+                if let Some(edge) = self.container.bounce(particle, &bounds, wall_restitution) {
+                    self.absorbed_counts[edge as usize] += 1;
+                    absorbed.push(i);
+                }
+                self.wall_pressure.record(particle, velocity);
+                if let Some(events) = self.collision_events.as_mut() {
+                    events.extend(CollisionEvent::off_wall(particle, velocity));
                 }
             }
+        } else {
+            for i in 0..self.particles.len() {
+                let (first, rest) = self.particles.split_at_mut(i + 1);
+                let circle = first.last_mut().unwrap();
+
+                circle.x += circle.dx;
+                circle.y += circle.dy;
+                circle.free_path += (circle.dx * circle.dx + circle.dy * circle.dy).sqrt();
+
+                for other_circle in rest.iter_mut() {
+                    if !circle.collides_with(other_circle) {
+                        continue;
+                    }
+
+                    let (dx, dy) = self.container.displacement(
+                        (circle.x, circle.y),
+                        (other_circle.x, other_circle.y),
+                        &bounds,
+                    );
+                    let distance = (dx * dx + dy * dy).sqrt();
+
+                    if matches!(
+                        self.collision_mode,
+                        CollisionMode::Bounce | CollisionMode::Fragment
+                    ) && distance < circle.radius + other_circle.radius
+                    {
+                        let velocities =
+                            ((circle.dx, circle.dy), (other_circle.dx, other_circle.dy));
+                        resolve_collision(
+                            circle,
+                            other_circle,
+                            (dx, dy),
+                            distance,
+                            self.coefficient_of_restitution,
+                        );
 
-            // Check for bouncing off edges
-            if circle.x + circle.radius > self.width || circle.x - circle.radius < 0.0 {
-                circle.dx *= -1.0;
+                        // Coincident particles have no normal to report.
+                        if let Some(events) = self.collision_events.as_mut() {
+                            if distance > 0.0 {
+                                let normal = (dx / distance, dy / distance);
+                                events.push(CollisionEvent::between(
+                                    circle,
+                                    other_circle,
+                                    velocities,
+                                    normal,
+                                ));
+                            }
+                        }
 
-                if !self.is_wall_elastic {
-                    circle.dx *= self.coefficient_of_restitution;
+                        for free_path in
+                            [circle.record_collision(), other_circle.record_collision()]
+                        {
+                            if let Some(statistics) = self.collision_statistics.as_mut() {
+                                statistics.add_free_path(free_path);
+                            }
+                        }
+                    }
                 }
-            }
-            if circle.y + circle.radius > self.height || circle.y - circle.radius < 0.0 {
-                circle.dy *= -1.0;
 
-                if !self.is_wall_elastic {
-                    circle.dy *= self.coefficient_of_restitution;
+                let velocity = (circle.dx, circle.dy);
+                if let Some(edge) = self.container.bounce(circle, &bounds, wall_restitution) {
+                    self.absorbed_counts[edge as usize] += 1;
+                    absorbed.push(i);
+                }
+                self.wall_pressure.record(circle, velocity);
+                if let Some(events) = self.collision_events.as_mut() {
+                    events.extend(CollisionEvent::off_wall(circle, velocity));
                 }
             }
+        }
 
-            if circle.x + circle.radius > self.width {
-                circle.x = self.width - circle.radius;
-            }
+        self.remove_particles_at(&absorbed);
 
-            if circle.x - circle.radius < 0.0 {
-                circle.x = circle.radius;
-            }
+        if self.collision_mode == CollisionMode::Merge {
+            let merged = merge_touching_particles(&mut self.particles, &self.container, &bounds);
+            self.remove_particles_at(&merged);
+        }
 
-            if circle.y + circle.radius > self.height {
-                circle.y = self.height - circle.radius;
-            }
+        // The position-based solver handles constraints and obstacles itself.
+        if self.solver == Solver::Impulse {
+            solve_constraints(
+                &self.constraints,
+                &mut self.particles,
+                &self.container,
+                &bounds,
+                self.constraint_iterations,
+            );
+
+            collide_particles_with_obstacles(
+                &mut self.particles,
+                &self.obstacles,
+                wall_restitution,
+            );
+        }
 
-            if circle.y - circle.radius < 0.0 {
-                circle.y = circle.radius;
+        tick_polygons(
+            &mut self.polygons,
+            &mut self.particles,
+            &self.obstacles,
+            &self.container,
+            &bounds,
+            self.coefficient_of_restitution,
+            wall_restitution,
+        );
+        let container = &self.container;
+        let absorbed_counts = &mut self.absorbed_counts;
+        self.polygons.retain(|polygon| {
+            match container.absorbing_edge(&polygon.bounding_box(), &bounds) {
+                Some(edge) => {
+                    absorbed_counts[edge as usize] += 1;
+                    false
+                }
+                None => true,
             }
+        });
+        write_vertex_buffer(&self.polygons, &mut self.polygon_vertices);
+
+        self.thermostat.apply(&mut self.particles);
+        self.wall_pressure.end_tick();
+        if let Some(radial_distribution) = self.radial_distribution.as_mut() {
+            radial_distribution.sample(&self.particles, &self.container, &bounds);
+        }
+        if let Some(transport) = self.transport.as_mut() {
+            transport.sample(&self.particles);
+        }
+        if let Some(statistics) = self.collision_statistics.as_mut() {
+            statistics.end_tick(self.particles.len());
+        }
+
+        update_particle_colors(&mut self.particles, &self.color_mapping);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_sorted_by_id(universe: &Universe) {
+        for pair in universe.particles.windows(2) {
+            assert!(pair[0].id < pair[1].id);
         }
+        for particle in universe.particles.iter() {
+            assert_eq!(
+                universe
+                    .get_particle_index(particle.id as u32)
+                    .map(|i| universe.particles[i].id),
+                Some(particle.id)
+            );
+        }
+    }
+
+    #[test]
+    fn emitted_particles_stay_sorted_by_id() {
+        let mut universe = Universe::new(200.0, 200.0);
+        universe.insert_particle(20.0, 20.0, 1.0, 0.0, 2.0);
+
+        let mut emitter = Emitter::new(100.0, 100.0);
+        emitter.rate = 3.0;
+        emitter.spread = std::f64::consts::PI;
+        emitter.lifetime = 5.0;
+        universe.add_emitter(emitter);
+
+        for _ in 0..20 {
+            universe.tick();
+            assert_sorted_by_id(&universe);
+        }
+        universe.insert_particle(50.0, 50.0, 0.0, 0.0, 2.0);
+        assert_sorted_by_id(&universe);
+    }
+
+    #[test]
+    fn emitted_particles_live_their_whole_lifetime() {
+        let mut universe = Universe::new(200.0, 200.0);
+        let mut emitter = Emitter::new(100.0, 100.0);
+        emitter.max_count = 1;
+        emitter.min_speed = 0.0;
+        emitter.max_speed = 0.0;
+        emitter.lifetime = 3.0;
+        universe.add_emitter(emitter);
+
+        let counts: Vec<usize> = (0..5)
+            .map(|_| {
+                universe.tick();
+                universe.particles.len()
+            })
+            .collect();
+        assert_eq!(counts, [1, 1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn handles_survive_earlier_removals() {
+        let mut universe = Universe::new(200.0, 200.0);
+        for x in [20.0, 40.0, 60.0] {
+            universe.insert_particle(x, 20.0, 0.0, 0.0, 2.0);
+        }
+
+        let first_emitter = universe.add_emitter(Emitter::new(10.0, 10.0));
+        let emitter = universe.add_emitter(Emitter::new(90.0, 90.0));
+        let first_force = universe.add_gravity(0.0, 1.0);
+        let force = universe.add_linear_drag(0.1);
+        let first_constraint = universe.add_rigid_link(0, 1, 0.0).unwrap();
+        let constraint = universe.add_spring(0, 2, 0.0, 0.5, 0.1).unwrap();
 
-        update_particle_colors(&mut self.particles);
+        assert!(universe.remove_emitter(first_emitter));
+        assert!(universe.remove_force(first_force));
+        assert!(universe.remove_constraint(first_constraint));
+        assert!(!universe.remove_emitter(first_emitter));
+        assert!(!universe.remove_force(first_force));
+        assert!(!universe.remove_constraint(first_constraint));
+
+        assert!(universe.update_emitter(emitter, Emitter::new(50.0, 50.0)));
+        assert_eq!(
+            universe
+                .get_emitter(emitter)
+                .map(|emitter| (emitter.get_id(), emitter.x1)),
+            Some((emitter, 50.0))
+        );
+        assert!(universe.set_force_region(force, 0.0, 0.0, 10.0, 10.0));
+        assert!(!universe.set_force_region(first_force, 0.0, 0.0, 10.0, 10.0));
+
+        assert!(universe.remove_particle(1));
+        assert_eq!(universe.get_constraint_ids(), [constraint]);
+        assert_eq!(universe.get_constraint_pairs(), [0, 2]);
+    }
+
+    #[test]
+    fn grabbing_returns_the_particle_id() {
+        let mut universe = Universe::new(200.0, 200.0);
+        universe.insert_particle(20.0, 20.0, 0.0, 0.0, 2.0);
+        universe.insert_particle(100.0, 100.0, 0.0, 0.0, 2.0);
+        universe.insert_particle(150.0, 150.0, 0.0, 0.0, 2.0);
+        assert!(universe.remove_particle(0));
+
+        assert_eq!(universe.grab_nearest_particle(101.0, 101.0, 5.0), Some(1));
+        assert_eq!(universe.pointer.grabbed, Some(0));
+        assert_eq!(universe.grab_nearest_particle(60.0, 60.0, 5.0), None);
+    }
+
+    #[test]
+    fn fragments_stay_sorted_by_id() {
+        let mut universe = Universe::new(200.0, 200.0);
+        universe.insert_particle(10.0, 10.0, 0.0, 0.0, 2.0);
+        universe.insert_particle(80.0, 100.0, 4.0, 0.0, 8.0);
+        universe.insert_particle(120.0, 100.0, -4.0, 0.0, 8.0);
+        universe.insert_particle(190.0, 190.0, 0.0, 0.0, 2.0);
+        universe.set_collision_mode(CollisionMode::Fragment);
+        universe.set_fragmentation(1.0, 4, 1.0, 0.5, ScatterDistribution::Even);
+
+        for _ in 0..10 {
+            universe.tick();
+            assert_sorted_by_id(&universe);
+        }
+        assert!(universe.particles.len() > 4);
+    }
+
+    #[test]
+    fn merged_particles_stay_sorted_by_id() {
+        let mut universe = Universe::new(200.0, 200.0);
+        universe.insert_particle(10.0, 10.0, 0.0, 0.0, 2.0);
+        universe.insert_particle(80.0, 100.0, 2.0, 0.0, 5.0);
+        universe.insert_particle(120.0, 100.0, -2.0, 0.0, 5.0);
+        universe.insert_particle(190.0, 190.0, 0.0, 0.0, 2.0);
+        universe.set_collision_mode(CollisionMode::Merge);
+
+        for _ in 0..20 {
+            universe.tick();
+            assert_sorted_by_id(&universe);
+        }
+        assert_eq!(universe.particles.len(), 3);
+        universe.insert_particle(50.0, 50.0, 0.0, 0.0, 2.0);
+        assert_sorted_by_id(&universe);
     }
 }
@@ -0,0 +1,91 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    observables::temperature,
+    particle::Particle,
+    utils::{get_random_in_range, get_random_normal},
+};
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThermostatKind {
+    Off = 0,
+    /// Rescales every velocity to hit the target exactly each tick.
+    VelocityRescaling = 1,
+    /// Rescales velocities part of the way, relaxing towards the target at
+    /// rate `coupling`.
+    Berendsen = 2,
+    /// Redraws each particle's velocity from the Maxwell-Boltzmann
+    /// distribution with probability `coupling` per tick.
+    Andersen = 3,
+    /// Friction `coupling` plus matching random kicks on every particle.
+    Langevin = 4,
+}
+
+/// Holds the particles at `target_temperature`, in the same units as
+/// [`temperature`](crate::observables::temperature).
+#[derive(Clone, Copy, Debug)]
+pub struct Thermostat {
+    pub kind: ThermostatKind,
+    pub target_temperature: f64,
+    pub coupling: f64,
+}
+
+impl Default for Thermostat {
+    fn default() -> Thermostat {
+        Thermostat {
+            kind: ThermostatKind::Off,
+            target_temperature: 1.0,
+            coupling: 0.1,
+        }
+    }
+}
+
+impl Thermostat {
+    /// Adjusts the velocities for one tick.
+    pub fn apply(&self, particles: &mut [Particle]) {
+        let target = self.target_temperature.max(0.0);
+        let coupling = self.coupling.clamp(0.0, 1.0);
+
+        match self.kind {
+            ThermostatKind::Off => {}
+            ThermostatKind::VelocityRescaling | ThermostatKind::Berendsen => {
+                let current = temperature(particles);
+                if current <= 0.0 {
+                    return;
+                }
+
+                let ratio = target / current;
+                let scale = if self.kind == ThermostatKind::VelocityRescaling {
+                    ratio.sqrt()
+                } else {
+                    (1.0 + coupling * (ratio - 1.0)).sqrt()
+                };
+                for particle in particles.iter_mut() {
+                    particle.dx *= scale;
+                    particle.dy *= scale;
+                }
+            }
+            ThermostatKind::Andersen => {
+                for particle in particles.iter_mut() {
+                    if get_random_in_range(0.0, 1.0) < coupling {
+                        let sigma = (target / particle.mass).sqrt();
+                        particle.dx = sigma * get_random_normal();
+                        particle.dy = sigma * get_random_normal();
+                    }
+                }
+            }
+            ThermostatKind::Langevin => {
+                // Exact update of the Ornstein-Uhlenbeck process over one tick,
+                // so large frictions stay stable.
+                let decay = (-coupling).exp();
+                let kick = (1.0 - decay * decay).sqrt();
+                for particle in particles.iter_mut() {
+                    let sigma = kick * (target / particle.mass).sqrt();
+                    particle.dx = decay * particle.dx + sigma * get_random_normal();
+                    particle.dy = decay * particle.dy + sigma * get_random_normal();
+                }
+            }
+        }
+    }
+}
@@ -1,27 +1,238 @@
 use crate::particle::Particle;
 
+#[cfg(target_arch = "wasm32")]
 use js_sys::Math;
+use wasm_bindgen::prelude::*;
 
-pub fn update_particle_colors(particles: &mut Vec<Particle>) {
-    for particle in particles.iter_mut() {
-        let speed = calculate_speed(&particle);
-        let color = get_color(speed); // Assuming max speed as 5.0
+/// Ramp that particle colors are picked from.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Colormap {
+    /// Dark blue to red.
+    BlueRed = 0,
+    Viridis = 1,
+    Plasma = 2,
+    Grayscale = 3,
+    /// Ten distinct colors picked by the integer part of the value, ignoring
+    /// the range; meant for groups and counts.
+    Categorical = 4,
+}
+
+/// Per-particle value that is mapped to a color.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorQuantity {
+    Speed = 0,
+    KineticEnergy = 1,
+    Mass = 2,
+    Group = 3,
+    /// Kinetic energy relative to the mean velocity of all particles, so a
+    /// flowing but cold gas stays cold.
+    Temperature = 4,
+    CollisionCount = 5,
+}
+
+/// Evenly spaced stops along matplotlib's viridis.
+const VIRIDIS: [[f64; 3]; 9] = [
+    [68.0, 1.0, 84.0],
+    [71.0, 44.0, 122.0],
+    [59.0, 81.0, 139.0],
+    [44.0, 113.0, 142.0],
+    [33.0, 144.0, 141.0],
+    [39.0, 173.0, 129.0],
+    [92.0, 200.0, 99.0],
+    [170.0, 220.0, 50.0],
+    [253.0, 231.0, 37.0],
+];
+
+/// Evenly spaced stops along matplotlib's plasma.
+const PLASMA: [[f64; 3]; 9] = [
+    [13.0, 8.0, 135.0],
+    [75.0, 3.0, 161.0],
+    [125.0, 3.0, 168.0],
+    [168.0, 34.0, 150.0],
+    [203.0, 70.0, 121.0],
+    [229.0, 107.0, 93.0],
+    [248.0, 148.0, 65.0],
+    [253.0, 195.0, 40.0],
+    [240.0, 249.0, 33.0],
+];
+
+/// Tableau 10.
+const CATEGORIES: [[f64; 3]; 10] = [
+    [31.0, 119.0, 180.0],
+    [255.0, 127.0, 14.0],
+    [44.0, 160.0, 44.0],
+    [214.0, 39.0, 40.0],
+    [148.0, 103.0, 189.0],
+    [140.0, 86.0, 75.0],
+    [227.0, 119.0, 194.0],
+    [127.0, 127.0, 127.0],
+    [188.0, 189.0, 34.0],
+    [23.0, 190.0, 207.0],
+];
+
+impl Colormap {
+    /// Color at `value`, which is scaled from `[min, max]` onto the ramp.
+    pub fn color(&self, value: f64, min: f64, max: f64) -> [f64; 3] {
+        let t = if max > min {
+            ((value - min) / (max - min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        match self {
+            Colormap::BlueRed => interpolate_color(t, [0.0, 0.0, 139.0], [255.0, 0.0, 0.0]),
+            Colormap::Viridis => sample_stops(&VIRIDIS, t),
+            Colormap::Plasma => sample_stops(&PLASMA, t),
+            Colormap::Grayscale => interpolate_color(t, [0.0, 0.0, 0.0], [255.0, 255.0, 255.0]),
+            Colormap::Categorical => {
+                let category = (value.floor() as i64).rem_euclid(CATEGORIES.len() as i64);
+                CATEGORIES[category as usize]
+            }
+        }
+    }
+}
+
+/// How particles are colored at the end of each tick. The default reproduces
+/// the original speed ramp.
+#[derive(Clone, Copy, Debug)]
+pub struct ColorMapping {
+    pub colormap: Colormap,
+    pub quantity: ColorQuantity,
+    pub min: f64,
+    pub max: f64,
+    /// Stretches the range over the values of the current tick instead.
+    pub auto_range: bool,
+    /// Leaves every particle's color as it is.
+    pub fixed: bool,
+}
+
+impl Default for ColorMapping {
+    fn default() -> ColorMapping {
+        ColorMapping {
+            colormap: Colormap::BlueRed,
+            quantity: ColorQuantity::Speed,
+            min: 0.0,
+            max: 5.0,
+            auto_range: false,
+            fixed: false,
+        }
+    }
+}
+
+pub fn update_particle_colors(particles: &mut [Particle], mapping: &ColorMapping) {
+    if mapping.fixed || particles.is_empty() {
+        return;
+    }
+
+    let mean_velocity = mean_velocity(particles);
+    let values: Vec<f64> = particles
+        .iter()
+        .map(|particle| color_value(particle, mapping.quantity, mean_velocity))
+        .collect();
+
+    let (min, max) = if mapping.auto_range {
+        values
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            })
+    } else {
+        (mapping.min, mapping.max)
+    };
+
+    for (particle, value) in particles.iter_mut().zip(values) {
+        let color = mapping.colormap.color(value, min, max);
         particle.color_r = color[0];
         particle.color_g = color[1];
         particle.color_b = color[2];
     }
 }
 
+/// Uniform sample from `[0, 1)`.
+#[cfg(target_arch = "wasm32")]
+fn random() -> f64 {
+    Math::random()
+}
+
+/// Native builds such as the test runner have no JavaScript `Math`, so they
+/// draw from a fixed-seed xorshift generator instead.
+#[cfg(not(target_arch = "wasm32"))]
+fn random() -> f64 {
+    use std::cell::Cell;
+
+    thread_local! {
+        static STATE: Cell<u64> = const { Cell::new(0x2545_f491_4f6c_dd1d) };
+    }
+
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        (x >> 11) as f64 / (1_u64 << 53) as f64
+    })
+}
+
 pub fn get_random_integer(min: i32, max: i32) -> f64 {
-    let x = Math::random();
+    let x = random();
     let y = (max - min) as f64;
     (x * y) + (min as f64)
 }
 
+pub fn get_random_in_range(min: f64, max: f64) -> f64 {
+    min + random() * (max - min)
+}
+
+/// Standard normal sample, from the Box-Muller transform.
+pub fn get_random_normal() -> f64 {
+    let u = 1.0 - random();
+    let v = random();
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+}
+
+/// Index of the item with `id` in `items`, which hand out ids in increasing
+/// order and so stay sorted by them.
+pub fn find_by_id<T>(items: &[T], id: u32, item_id: impl Fn(&T) -> u32) -> Option<usize> {
+    items.binary_search_by_key(&id, item_id).ok()
+}
+
 fn calculate_speed(particle: &Particle) -> f64 {
     (particle.dx.powi(2) + particle.dy.powi(2)).sqrt()
 }
 
+/// Velocity of the center of mass.
+fn mean_velocity(particles: &[Particle]) -> (f64, f64) {
+    let mut momentum = (0.0, 0.0);
+    let mut mass = 0.0;
+    for particle in particles.iter() {
+        momentum.0 += particle.mass * particle.dx;
+        momentum.1 += particle.mass * particle.dy;
+        mass += particle.mass;
+    }
+    if mass == 0.0 {
+        return (0.0, 0.0);
+    }
+    (momentum.0 / mass, momentum.1 / mass)
+}
+
+fn color_value(particle: &Particle, quantity: ColorQuantity, mean_velocity: (f64, f64)) -> f64 {
+    match quantity {
+        ColorQuantity::Speed => calculate_speed(particle),
+        ColorQuantity::KineticEnergy => 0.5 * particle.mass * calculate_speed(particle).powi(2),
+        ColorQuantity::Mass => particle.mass,
+        ColorQuantity::Group => particle.group,
+        ColorQuantity::Temperature => {
+            let dx = particle.dx - mean_velocity.0;
+            let dy = particle.dy - mean_velocity.1;
+            0.5 * particle.mass * (dx * dx + dy * dy)
+        }
+        ColorQuantity::CollisionCount => particle.collision_count,
+    }
+}
+
 fn interpolate_color(value: f64, color1: [f64; 3], color2: [f64; 3]) -> [f64; 3] {
     let r = color1[0] + value * (color2[0] - color1[0]);
     let g = color1[1] + value * (color2[1] - color1[1]);
@@ -29,9 +240,9 @@ fn interpolate_color(value: f64, color1: [f64; 3], color2: [f64; 3]) -> [f64; 3]
     [r, g, b]
 }
 
-fn get_color(speed: f64) -> [f64; 3] {
-    let normalized_speed = speed / 5.0;
-    let low_color: [f64; 3] = [0.0, 0.0, 139.0]; // Dark Blue
-    let high_color: [f64; 3] = [255.0, 0.0, 0.0]; // Red
-    interpolate_color(normalized_speed.min(1.0), low_color, high_color)
+/// Linear interpolation between evenly spaced `stops` at `t` in `[0, 1]`.
+fn sample_stops(stops: &[[f64; 3]], t: f64) -> [f64; 3] {
+    let position = t * (stops.len() - 1) as f64;
+    let index = (position.floor() as usize).min(stops.len() - 2);
+    interpolate_color(position - index as f64, stops[index], stops[index + 1])
 }
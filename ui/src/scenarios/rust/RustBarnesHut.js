@@ -1,7 +1,10 @@
 /* eslint-disable react-hooks/exhaustive-deps */
 import { useEffect, useState } from "react";
 
-import init, { BarnesUniverse as Universe } from "collisions-src";
+import init, {
+  BarnesUniverse as Universe,
+  particle_stride,
+} from "collisions-src";
 import Dashboard from "../../Dashboard";
 import { getCanvas2dContext, getCanvasDimensions } from "../../utils";
 
@@ -86,7 +89,7 @@ const RustBarnesHut = () => {
 
     setNumberOfParticles(particlesLen);
 
-    const numberOfFields = 8;
+    const numberOfFields = particle_stride();
 
     const memoryBuffer = new Float64Array(
       wasmMemory.buffer,
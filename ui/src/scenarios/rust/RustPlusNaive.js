@@ -1,7 +1,7 @@
 /* eslint-disable react-hooks/exhaustive-deps */
 import { useEffect, useState } from "react";
 
-import init, { Universe } from "collisions-src";
+import init, { Universe, particle_stride } from "collisions-src";
 import Dashboard from "../../Dashboard";
 import { getCanvas2dContext, getCanvasDimensions } from "../../utils";
 
@@ -86,7 +86,7 @@ const RustPlusNaive = () => {
 
     setNumberOfParticles(particlesLen);
 
-    const numberOfFields = 8;
+    const numberOfFields = particle_stride();
 
     const memoryBuffer = new Float64Array(
       wasmMemory.buffer,